@@ -2,10 +2,12 @@ use leptos::prelude::*;
 use leptos_meta::{Stylesheet, Title, provide_meta_context};
 use leptos_mview::mview;
 use leptos_router::{
-    StaticSegment,
+    ParamSegment, StaticSegment,
     components::{Route, Router, Routes},
+    hooks::use_params_map,
 };
 
+use crate::library::Library;
 use crate::reader::Reader;
 
 #[component]
@@ -14,20 +16,46 @@ pub fn App() -> impl IntoView {
 
     mview! {
         Stylesheet href="/pkg/app.css";
-        Title text="Literacy";
+        Title formatter={format_title} text="";
         Router {
             main {
                 Routes fallback={|| "Page not found.".into_view()} {
-                    Route path={StaticSegment("")} view={ReadingPage};
+                    Route path={StaticSegment("")} view={LibraryPage};
+                    Route path={(StaticSegment("read"), ParamSegment("text_id"))} view={ReadingPage};
                 }
             }
         }
     }
 }
 
+fn format_title(title: String) -> String {
+    if title.is_empty() {
+        "Literacy".to_string()
+    } else {
+        format!("{title} · Literacy")
+    }
+}
+
+#[component]
+fn LibraryPage() -> impl IntoView {
+    mview! {
+        Library;
+    }
+}
+
 #[component]
 fn ReadingPage() -> impl IntoView {
+    let params = use_params_map();
+    let text_id = move || params.read().get("text_id").unwrap_or_default();
+
     mview! {
-        Reader;
+        {
+            move || {
+                let text_id = text_id();
+                mview! {
+                    Reader {text_id};
+                }
+            }
+        }
     }
 }
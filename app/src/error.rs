@@ -0,0 +1,72 @@
+use leptos::prelude::*;
+use leptos_mview::mview;
+use thiserror::Error;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::js_sys;
+
+/// Failures loading and playing texts, shown in the page instead of panicking.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub(crate) enum Error {
+    #[error("failed to load {url}: {message}")]
+    Network { url: String, message: String },
+    #[error("failed to load {url}: status {status}")]
+    Status { url: String, status: u16 },
+    #[error("timed out loading {0}")]
+    Timeout(String),
+    #[error("failed to decode {what}: {message}")]
+    Decode { what: String, message: String },
+    #[error("audio failed: {0}")]
+    Audio(String),
+}
+
+impl Error {
+    pub(crate) fn network(url: &str, e: JsValue) -> Self {
+        Error::Network {
+            url: url.to_string(),
+            message: message(e),
+        }
+    }
+
+    pub(crate) fn decode(what: &str, e: impl ToString) -> Self {
+        Error::Decode {
+            what: what.to_string(),
+            message: e.to_string(),
+        }
+    }
+
+    pub(crate) fn audio(e: JsValue) -> Self {
+        Error::Audio(message(e))
+    }
+
+    /// Whether trying again may succeed, like for a lost connection or an
+    /// overloaded server.
+    pub(crate) fn is_transient(&self) -> bool {
+        match self {
+            Error::Network { .. } | Error::Timeout(_) => true,
+            Error::Status { status, .. } => matches!(status, 408 | 429 | 500..=599),
+            Error::Decode { .. } | Error::Audio(_) => false,
+        }
+    }
+}
+
+/// Message of an exception thrown by a browser api.
+pub(crate) fn message(e: JsValue) -> String {
+    e.dyn_ref::<js_sys::Error>()
+        .map(|e| String::from(e.message()))
+        .or_else(|| e.as_string())
+        .unwrap_or_else(|| format!("{e:?}"))
+}
+
+/// Inline error message with a button to try again.
+#[component]
+pub(crate) fn ErrorMessage(
+    #[prop(into)] error: Signal<Option<Error>>,
+    retry: impl Fn() + 'static,
+) -> impl IntoView {
+    mview! {
+        div.error class:open={move || error.with(Option::is_some)} {
+            p { {move || error.get().map(|e| e.to_string())} }
+            button on:click={move |_| retry()} { "retry" }
+        }
+    }
+}
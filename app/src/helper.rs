@@ -0,0 +1,281 @@
+use std::{cell::Cell, time::Duration};
+
+use leptos::{
+    logging,
+    prelude::{TimeoutHandle, set_timeout, set_timeout_with_handle},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    AbortController, Blob, BlobPropertyBag, Headers, HtmlAnchorElement,
+    ReadableStreamDefaultReader, ReadableStreamReadResult, RequestInit, Response, Storage, Url,
+    js_sys::{Array, JsString, Promise, Uint8Array},
+    window,
+};
+
+use crate::error::Error;
+
+/// Time without any data arriving after which a request is aborted.
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// Attempts after the first one for requests failing transiently.
+const RETRIES: u32 = 3;
+/// Wait before the first retry, doubled for each one after.
+const BACKOFF: Duration = Duration::from_millis(500);
+
+/// Bytes of a download received so far and in total, if known.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Progress {
+    pub(crate) loaded: u64,
+    pub(crate) total: Option<u64>,
+}
+
+impl Progress {
+    pub(crate) fn fraction(&self) -> Option<f64> {
+        let total = self.total.filter(|t| *t > 0)?;
+        Some((self.loaded as f64 / total as f64).min(1.0))
+    }
+}
+
+/// Aborts a request once no data arrived for [`TIMEOUT`].
+struct Watchdog {
+    controller: AbortController,
+    timer: Cell<Option<TimeoutHandle>>,
+}
+
+impl Watchdog {
+    fn new(url: &str) -> Result<Self, Error> {
+        let watchdog = Self {
+            controller: AbortController::new().map_err(|e| Error::network(url, e))?,
+            timer: Cell::new(None),
+        };
+        watchdog.reset();
+        Ok(watchdog)
+    }
+
+    /// Restarts the timeout, after data arrived.
+    fn reset(&self) {
+        if let Some(timer) = self.timer.take() {
+            timer.clear();
+        }
+        let controller = self.controller.clone();
+        self.timer
+            .set(set_timeout_with_handle(move || controller.abort(), TIMEOUT).ok());
+    }
+
+    fn error(&self, url: &str, e: JsValue) -> Error {
+        if self.controller.signal().aborted() {
+            Error::Timeout(url.to_string())
+        } else {
+            Error::network(url, e)
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.clear();
+        }
+    }
+}
+
+/// Waits `duration` without blocking the page.
+pub(crate) async fn sleep(duration: Duration) {
+    let promise = Promise::new(&mut |resolve, _| {
+        set_timeout(
+            move || {
+                _ = resolve.call0(&JsValue::NULL);
+            },
+            duration,
+        )
+    });
+    _ = JsFuture::from(promise).await;
+}
+
+/// Runs `attempt` until it succeeds or fails for good, waiting longer after
+/// every transient failure.
+async fn with_retries<T, F, R>(attempt: F) -> Result<T, Error>
+where
+    F: Fn() -> R,
+    R: Future<Output = Result<T, Error>>,
+{
+    let mut backoff = BACKOFF;
+    let mut retries = 0;
+    loop {
+        match attempt().await {
+            Err(e) if e.is_transient() && retries < RETRIES => {
+                logging::warn!("{e}, retrying in {backoff:?}");
+                sleep(backoff).await;
+                backoff *= 2;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn request(url: &str, watchdog: &Watchdog) -> Result<Response, Error> {
+    let window = window().ok_or_else(|| Error::network(url, "no window".into()))?;
+    let init = RequestInit::new();
+    init.set_signal(Some(&watchdog.controller.signal()));
+    let response: Response = JsFuture::from(window.fetch_with_str_and_init(url, &init))
+        .await
+        .and_then(|r| r.dyn_into())
+        .map_err(|e| watchdog.error(url, e))?;
+    if !response.ok() {
+        return Err(Error::Status {
+            url: url.to_string(),
+            status: response.status(),
+        });
+    }
+    Ok(response)
+}
+
+/// Loads the bytes at `url`, calling `progress` whenever some arrived.
+pub(crate) async fn load_bytes(url: &str, progress: impl Fn(Progress)) -> Result<Vec<u8>, Error> {
+    let progress = &progress;
+    with_retries(move || fetch_with_progress(url, progress)).await
+}
+
+async fn fetch_with_progress(url: &str, progress: &impl Fn(Progress)) -> Result<Vec<u8>, Error> {
+    let watchdog = Watchdog::new(url)?;
+    let response = request(url, &watchdog).await?;
+    let total = response
+        .headers()
+        .get("Content-Length")
+        .ok()
+        .flatten()
+        .and_then(|l| l.parse().ok());
+    let Some(body) = response.body() else {
+        return Ok(vec![]);
+    };
+    let reader: ReadableStreamDefaultReader = body.get_reader().unchecked_into();
+    let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+    loop {
+        let chunk: ReadableStreamReadResult = JsFuture::from(reader.read())
+            .await
+            .map_err(|e| watchdog.error(url, e))?
+            .unchecked_into();
+        if chunk.get_done().unwrap_or(true) {
+            break;
+        }
+        bytes.extend(Uint8Array::new(&chunk.get_value()).to_vec());
+        watchdog.reset();
+        progress(Progress {
+            loaded: bytes.len() as u64,
+            total,
+        });
+    }
+    Ok(bytes)
+}
+
+pub(crate) async fn load_text(url: &str) -> Result<String, Error> {
+    with_retries(|| async move {
+        let watchdog = Watchdog::new(url)?;
+        let response = request(url, &watchdog).await?;
+        let promise = response.text().map_err(|e| Error::network(url, e))?;
+        let text = JsFuture::from(promise)
+            .await
+            .map_err(|e| watchdog.error(url, e))?
+            .dyn_into::<JsString>()
+            .map_err(|e| Error::network(url, e))?;
+        Ok(text.into())
+    })
+    .await
+}
+
+/// Size in bytes of the file at `url`, `None` if the server doesn't tell.
+pub(crate) async fn content_length(url: &str) -> Option<u64> {
+    let response = send("HEAD", url, None).await?;
+    if !response.ok() {
+        return None;
+    }
+    response.headers().get("Content-Length").ok()??.parse().ok()
+}
+
+/// Sends a request with an optional JSON body, `None` if it couldn't be sent.
+pub(crate) async fn send(method: &str, url: &str, json: Option<&str>) -> Option<Response> {
+    let init = RequestInit::new();
+    init.set_method(method);
+    if let Some(json) = json {
+        let headers = Headers::new().ok()?;
+        headers.set("Content-Type", "application/json").ok()?;
+        init.set_headers(&headers);
+        init.set_body(&JsValue::from_str(json));
+    }
+    let promise = window()?.fetch_with_str_and_init(url, &init);
+    JsFuture::from(promise).await.ok()?.dyn_into().ok()
+}
+
+/// Reads the body of a successful response as text.
+pub(crate) async fn response_text(response: Response) -> Option<String> {
+    if !response.ok() {
+        return None;
+    }
+    let text = JsFuture::from(response.text().ok()?).await.ok()?;
+    text.as_string()
+}
+
+/// Resolves a reference from a text document against the folder of the text.
+pub(crate) fn resolve(base: &str, reference: &str) -> String {
+    if reference.starts_with('/') || reference.contains("://") {
+        reference.to_string()
+    } else {
+        format!(
+            "{}/{}",
+            base.trim_end_matches('/'),
+            reference.trim_start_matches("./")
+        )
+    }
+}
+
+pub(crate) fn set_document_language(language: &str) {
+    if let Some(e) = window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.document_element())
+    {
+        _ = e.set_attribute("lang", language);
+    }
+}
+
+fn storage() -> Option<Storage> {
+    window()?.local_storage().ok()?
+}
+
+/// Reads a value saved in the browser.
+pub(crate) fn load_local<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let json = storage()?.get_item(key).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+/// Saves a value in the browser, failures like a full storage are ignored.
+pub(crate) fn save_local<T: Serialize>(key: &str, value: &T) {
+    if let Some(s) = storage()
+        && let Ok(json) = serde_json::to_string(value)
+    {
+        _ = s.set_item(key, &json);
+    }
+}
+
+/// Offers `content` as a file download.
+pub(crate) fn download(file_name: &str, media_type: &str, content: &str) -> Option<()> {
+    let options = BlobPropertyBag::new();
+    options.set_type(media_type);
+    let blob =
+        Blob::new_with_str_sequence_and_options(&Array::of1(&JsValue::from_str(content)), &options)
+            .ok()?;
+    let url = Url::create_object_url_with_blob(&blob).ok()?;
+    let anchor: HtmlAnchorElement = window()?
+        .document()?
+        .create_element("a")
+        .ok()?
+        .dyn_into()
+        .ok()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    _ = Url::revoke_object_url(&url);
+    Some(())
+}
@@ -6,4 +6,11 @@ pub use shell::shell;
 pub mod app;
 pub use app::App;
 
+mod error;
+mod helper;
+
+pub mod library;
+
+pub mod position;
+
 pub mod reader;
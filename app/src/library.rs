@@ -0,0 +1,77 @@
+use common::LibraryEntry;
+use leptos::prelude::*;
+use leptos_mview::mview;
+
+use crate::{
+    error::{Error, ErrorMessage},
+    helper,
+};
+
+/// Route under which the server exposes the folders of all texts.
+pub const CONTENT_ROUTE: &str = "/content";
+
+/// Route under which the server lists all texts as json.
+pub const LIBRARY_ROUTE: &str = "/api/texts";
+
+pub(crate) fn text_base(text_id: &str) -> String {
+    format!("{CONTENT_ROUTE}/{text_id}")
+}
+
+#[component]
+pub(crate) fn Library() -> impl IntoView {
+    let entries_resource: LocalResource<Result<Vec<LibraryEntry>, Error>> =
+        LocalResource::new(async move || {
+            let json = helper::load_text(LIBRARY_ROUTE).await?;
+            serde_json::from_str(&json).map_err(|e| Error::decode("library", e))
+        });
+    let entries = move || entries_resource.get().map(|e| e.take());
+    let error = Signal::derive(move || entries().and_then(Result::err));
+
+    mview! {
+        div.library {
+            ErrorMessage {error} retry={move || entries_resource.refetch()};
+            ul.entries {
+                {
+                    move || entries()
+                    .and_then(Result::ok)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|e| mview! {
+                        Entry entry={e};
+                    }).collect_view()
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn Entry(entry: LibraryEntry) -> impl IntoView {
+    let href = format!("/read/{}", entry.id);
+    let title = entry.title().to_string();
+    let cover = entry
+        .metadata
+        .cover
+        .as_ref()
+        .map(|c| helper::resolve(&text_base(&entry.id), c));
+    let details = [
+        entry.metadata.author.clone(),
+        entry.metadata.language.clone(),
+        entry.metadata.level.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ");
+    let language = entry.metadata.language.clone();
+
+    mview! {
+        li.entry {
+            a {href} {
+                {cover.map(|src| mview! { img.cover {src} alt=""; })}
+                span.title lang={language} { {title} }
+                span.details { {details} }
+            }
+        }
+    }
+}
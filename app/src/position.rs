@@ -0,0 +1,43 @@
+use common::Position;
+use web_sys::js_sys::Date;
+
+use crate::helper;
+
+pub const POSITIONS_ROUTE: &str = "/api/positions";
+
+const STORAGE_PREFIX: &str = "literacy.position.";
+
+/// Milliseconds since the Unix epoch, for [`Position::updated`].
+pub(crate) fn now() -> u64 {
+    Date::now() as u64
+}
+
+/// Position saved in the browser.
+pub(crate) fn load_local(text_id: &str) -> Option<Position> {
+    helper::load_local(&format!("{STORAGE_PREFIX}{text_id}"))
+}
+
+pub(crate) fn save_local(text_id: &str, position: &Position) {
+    helper::save_local(&format!("{STORAGE_PREFIX}{text_id}"), position)
+}
+
+/// Position saved on the server for the signed in user, `None` if there is
+/// none or the server doesn't keep positions.
+pub(crate) async fn load_remote(text_id: &str) -> Option<Position> {
+    let response = helper::send("GET", &format!("{POSITIONS_ROUTE}/{text_id}"), None).await?;
+    serde_json::from_str(&helper::response_text(response).await?).ok()
+}
+
+pub(crate) async fn save_remote(text_id: &str, position: &Position) {
+    if let Ok(json) = serde_json::to_string(position) {
+        _ = helper::send("PUT", &format!("{POSITIONS_ROUTE}/{text_id}"), Some(&json)).await;
+    }
+}
+
+/// The more recently updated of two positions.
+pub(crate) fn latest(a: Option<Position>, b: Option<Position>) -> Option<Position> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.updated > a.updated { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
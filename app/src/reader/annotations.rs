@@ -0,0 +1,163 @@
+use common::{
+    Text,
+    annotation::{self, Annotation, Color, Kind, Location},
+};
+use leptos::{ev, prelude::*};
+use leptos_mview::mview;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, Node, window};
+
+use crate::{helper, position};
+
+const STORAGE_PREFIX: &str = "literacy.annotations.";
+
+pub(crate) fn load(text_id: &str) -> Vec<Annotation> {
+    helper::load_local(&format!("{STORAGE_PREFIX}{text_id}")).unwrap_or_default()
+}
+
+pub(crate) fn save(text_id: &str, annotations: &[Annotation]) {
+    helper::save_local(&format!("{STORAGE_PREFIX}{text_id}"), &annotations)
+}
+
+/// First and last word of the text selected in the page, if any.
+pub(crate) fn selected_words() -> Option<(Location, Location)> {
+    let selection = window()?.get_selection().ok()??;
+    if selection.is_collapsed() {
+        return None;
+    }
+    let a = word_location(selection.anchor_node()?)?;
+    let b = word_location(selection.focus_node()?)?;
+    Some((a.min(b), a.max(b)))
+}
+
+/// Location of the word a node of the page is part of.
+fn word_location(node: Node) -> Option<Location> {
+    let element = match node.dyn_into::<Element>() {
+        Ok(element) => element,
+        Err(node) => node.parent_element()?,
+    };
+    let word = element.closest(".word").ok()??;
+    Some(Location {
+        segment: word.get_attribute("data-segment")?.parse().ok()?,
+        word: word.get_attribute("data-word")?.parse().ok()?,
+    })
+}
+
+/// Side panel to bookmark and highlight the selected words or the current
+/// segment, and to list, jump to and export the annotations.
+#[component]
+pub(crate) fn Annotations(
+    text_id: String,
+    #[prop(into)] text: Signal<Option<Text>>,
+    #[prop(into)] annotations: RwSignal<Vec<Annotation>>,
+    #[prop(into)] page: Signal<usize>,
+    /// Set to the segment and word of an annotation to go to.
+    #[prop(into)]
+    jump: RwSignal<Option<(usize, u32)>>,
+    #[prop(into)] open: Signal<bool>,
+) -> impl IntoView {
+    let note = RwSignal::new(String::new());
+
+    let add = move |kind: Kind| {
+        let (start, end) = match selected_words() {
+            Some(range) => range,
+            None => {
+                let segment = page.get_untracked();
+                let words = text.with_untracked(|t| {
+                    t.as_ref()
+                        .and_then(|t| t.segments.get(segment))
+                        .map_or(0, |s| s.words.len())
+                });
+                (
+                    Location { segment, word: 0 },
+                    Location {
+                        segment,
+                        word: words.saturating_sub(1),
+                    },
+                )
+            }
+        };
+        let text = note.get_untracked();
+        annotations.update(|a| {
+            a.push(Annotation {
+                kind,
+                start,
+                end,
+                note: (!text.trim().is_empty()).then(|| text.trim().to_string()),
+                created: position::now(),
+            })
+        });
+        note.set(String::new());
+    };
+
+    let export = move |_| {
+        if let Some(t) = text.get_untracked() {
+            let markdown = annotations.with_untracked(|a| annotation::to_markdown(&t, a));
+            helper::download(
+                &format!("{text_id}-annotations.md"),
+                "text/markdown",
+                &markdown,
+            );
+        }
+    };
+
+    // keep the selection in the text when pressing buttons
+    let keep_selection = |e: ev::MouseEvent| e.prevent_default();
+
+    let entries = move || {
+        let mut entries: Vec<(usize, Annotation)> =
+            annotations.get().into_iter().enumerate().collect();
+        entries.sort_by_key(|(_, a)| (a.start, a.end));
+        entries
+            .into_iter()
+            .map(|(i, a)| {
+                let excerpt = text.with(|t| t.as_ref().map(|t| a.excerpt(t)).unwrap_or_default());
+                let color = match a.kind {
+                    Kind::Bookmark => "bookmark",
+                    Kind::Highlight(c) => c.name(),
+                };
+                let target = (a.start.segment, a.start.word as u32);
+                mview! {
+                    li.annotation data-color={color} {
+                        button.excerpt on:click={move |_| jump.set(Some(target))} {
+                            span.segment { {format!("{} ", a.start.segment + 1)} }
+                            { excerpt }
+                        }
+                        { a.note.clone().map(|n| mview! { p.note { {n} } }) }
+                        button.remove
+                            title="remove"
+                            on:click={move |_| annotations.update(|a| { a.remove(i); })} {
+                            "×"
+                        }
+                    }
+                }
+            })
+            .collect_view()
+    };
+
+    mview! {
+        aside.annotations class:open={open} {
+            div.actions {
+                input.note type="text" placeholder="note" bind:value={note};
+                button.add
+                    data-color="bookmark"
+                    title="bookmark the selected words or the current segment"
+                    on:mousedown={keep_selection}
+                    on:click={move |_| add(Kind::Bookmark)} {
+                    "bookmark"
+                }
+                {
+                    Color::ALL.into_iter().map(|c| mview! {
+                        button.add
+                            data-color={c.name()}
+                            title={format!("highlight in {}", c.name())}
+                            on:mousedown={keep_selection}
+                            on:click={move |_| add(Kind::Highlight(c))};
+                    }).collect_view()
+                }
+                button.export on:click={export} { "export" }
+            }
+            ul { {entries} }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Loudness normalization, so segments from different voices and recordings
+//! play at about the same level.
+
+/// Length of the blocks loudness is measured in, in seconds.
+const BLOCK: f32 = 0.05;
+/// Mean square below which a block counts as silence, about -60 dBFS.
+const SILENCE: f64 = 1e-6;
+/// Loudness segments are brought to, as root mean square of the speech.
+const TARGET: f64 = 0.1;
+/// Most a quiet segment is amplified, so noise isn't blown up.
+const MAX_GAIN: f64 = 4.0;
+
+/// Gain bringing the speech in `channels` to the target loudness. Silent
+/// blocks between words are left out of the measurement and the gain is
+/// limited so the loudest sample doesn't clip.
+pub(super) fn normalization(channels: &[Vec<f32>], sample_rate: f32) -> f32 {
+    let block = ((sample_rate * BLOCK) as usize).max(1);
+    let mut power = 0.0;
+    let mut blocks = 0;
+    let mut peak = 0.0f32;
+    for channel in channels {
+        for samples in channel.chunks(block) {
+            let square = samples.iter().map(|s| (*s as f64).powi(2)).sum::<f64>();
+            let mean = square / samples.len() as f64;
+            if mean > SILENCE {
+                power += mean;
+                blocks += 1;
+            }
+            peak = samples.iter().fold(peak, |p, s| p.max(s.abs()));
+        }
+    }
+    if blocks == 0 {
+        return 1.0;
+    }
+    let rms = (power / blocks as f64).sqrt();
+    (TARGET / rms).min(MAX_GAIN).min(1.0 / peak as f64) as f32
+}
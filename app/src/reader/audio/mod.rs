@@ -0,0 +1,779 @@
+use leptos::{logging, prelude::*, task::spawn_local};
+use leptos_use::{
+    UseIntervalFnOptions, UseRafFnOptions, use_document_visibility, use_interval_fn_with_options,
+    use_raf_fn_with_options, utils::Pausable,
+};
+use std::cell::RefCell;
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+use wasm_bindgen::{JsCast, prelude::Closure};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::Uint8Array;
+use web_sys::{AudioBuffer, AudioBufferSourceNode, AudioContext, GainNode, VisibilityState};
+
+use crate::error::{self, Error};
+use crate::helper;
+use registry::{Key, Registry};
+use stream::{StreamInner, StreamInternal};
+use stretch::Stretch;
+
+mod loudness;
+mod registry;
+mod stream;
+mod stretch;
+
+/// Milliseconds between progress updates while the page is hidden.
+const HIDDEN_INTERVAL: u64 = 250;
+/// Seconds of audio stretched at a time before the page gets to run again.
+const STRETCH_STEP: f32 = 2.0;
+
+/// Playback rates selectable in the reader.
+pub(crate) const RATES: [f64; 7] = [0.5, 0.75, 0.9, 1.0, 1.25, 1.5, 2.0];
+
+/// Sets the volume of all tracks from 0 to 1, 0 mutes them.
+pub(crate) fn set_volume(volume: f64) -> Result<(), Error> {
+    with_player(|p| {
+        // ramp to the volume in a few milliseconds instead of clicking
+        p.volume
+            .gain()
+            .set_target_at_time(volume as f32, p.context.current_time(), 0.01)
+            .map(|_| ())
+            .map_err(Error::audio)
+    })
+}
+
+/// Sets `progress` to the position of `track` while `playing`, on every
+/// animation frame while the page is visible. Browsers don't run animation
+/// frames in hidden tabs or on the lock screen, so progress is polled every
+/// [`HIDDEN_INTERVAL`] milliseconds there to keep going on to the next segment.
+/// Failures of the track while playing are passed to `error`.
+pub(crate) fn publish_progress(
+    track: Signal<Option<Track>>,
+    playing: Signal<bool>,
+    progress: RwSignal<Option<f64>>,
+    error: RwSignal<Option<Error>>,
+) {
+    let update = move || {
+        track.with_untracked(|t| {
+            let Some(t) = t else {
+                return;
+            };
+            if let Some(p) = t.progress() {
+                progress.set(Some(p));
+            }
+            if let Some(e) = t.take_error() {
+                logging::error!("{e}");
+                error.set(Some(e));
+            }
+        });
+    };
+    let Pausable {
+        pause: pause_frames,
+        resume: resume_frames,
+        ..
+    } = use_raf_fn_with_options(
+        move |_| update(),
+        UseRafFnOptions::default().immediate(false),
+    );
+    let Pausable {
+        pause: pause_interval,
+        resume: resume_interval,
+        ..
+    } = use_interval_fn_with_options(
+        update,
+        HIDDEN_INTERVAL,
+        UseIntervalFnOptions::default().immediate(false),
+    );
+    let visibility = use_document_visibility();
+    Effect::new(move || {
+        let hidden = visibility.get() == VisibilityState::Hidden;
+        match (playing.get(), hidden) {
+            (true, false) => {
+                pause_interval();
+                resume_frames();
+            }
+            (true, true) => {
+                pause_frames();
+                resume_interval();
+            }
+            (false, _) => {
+                pause_frames();
+                pause_interval();
+            }
+        }
+    });
+}
+
+thread_local! {
+    static AUDIO_PLAYER_INTERNAL: RefCell<Result<AudioPlayer, Error>> = {
+        RefCell::new(AudioPlayer::new())
+    };
+}
+
+/// Runs `f` with the player, failing instead of panicking if audio isn't
+/// available or the player is already in use.
+fn with_player<T, R>(f: T) -> Result<R, Error>
+where
+    T: FnOnce(&mut AudioPlayer) -> Result<R, Error>,
+{
+    AUDIO_PLAYER_INTERNAL
+        .try_with(|p| {
+            let mut p = p
+                .try_borrow_mut()
+                .map_err(|_| Error::Audio("audio player is busy".to_string()))?;
+            f(p.as_mut().map_err(|e| e.clone())?)
+        })
+        .unwrap_or_else(|_| Err(Error::Audio("audio player is gone".to_string())))
+}
+
+#[derive(Debug)]
+struct AudioPlayer {
+    context: AudioContext,
+    /// Volume set by the user, all tracks play through it.
+    volume: GainNode,
+    tracks: Registry<TrackInternal>,
+    /// Decoded files shared by the tracks playing windows of them.
+    files: Registry<AudioBuffer>,
+    streams: Registry<StreamInternal>,
+    /// Playback rate of all tracks, pitch is preserved by time stretching.
+    rate: f64,
+}
+
+impl AudioPlayer {
+    fn new() -> Result<Self, Error> {
+        let context = AudioContext::new().map_err(Error::audio)?;
+        let volume = context.create_gain().map_err(Error::audio)?;
+        volume
+            .connect_with_audio_node(&context.destination())
+            .map_err(Error::audio)?;
+        Ok(Self {
+            context,
+            volume,
+            tracks: Registry::new(),
+            files: Registry::new(),
+            streams: Registry::new(),
+            rate: 1.0,
+        })
+    }
+}
+
+/// What a track is doing, times are context times in seconds of the buffer
+/// playing, positions in seconds of the source.
+#[derive(Debug)]
+enum State {
+    /// The source is being decoded.
+    Loading,
+    /// At the start of the source.
+    Ready,
+    /// Playing or scheduled to play.
+    Playing {
+        node: AudioBufferSourceNode,
+        /// Handler of the node reaching the end of the buffer.
+        ended: Closure<dyn FnMut()>,
+        /// Time at which the start of the source would have played.
+        start_time: f64,
+        /// Rate of the buffer playing.
+        rate: f64,
+        /// Whether the source plays sped up by the node, changing its pitch,
+        /// until it is stretched to `rate`.
+        native: bool,
+    },
+    Paused {
+        offset: f64,
+    },
+    /// Played to the end. The clock keeps running from `start_time`, so
+    /// pauses after the track can be timed by its progress.
+    Ended {
+        start_time: f64,
+        rate: f64,
+    },
+    /// Playing failed, playing again starts over.
+    Error(Error),
+}
+
+impl State {
+    /// Position in the source if the clock is running.
+    fn progress(&self, now: f64) -> Option<f64> {
+        match self {
+            State::Playing {
+                start_time, rate, ..
+            }
+            | State::Ended { start_time, rate } => Some((now - start_time) * rate),
+            _ => None,
+        }
+    }
+
+    /// Stops the node of a playing track, it won't report ending anymore.
+    fn halt(self) -> Result<(), Error> {
+        if let State::Playing { node, .. } = self {
+            node.set_onended(None);
+            #[allow(deprecated)]
+            node.stop().map_err(Error::audio)?;
+            node.disconnect().map_err(Error::audio)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct TrackInternal {
+    source: Option<AudioBuffer>,
+    /// `source` stretched to the rates it played at.
+    stretched: Vec<(f64, AudioBuffer)>,
+    /// Rates `source` is being stretched to.
+    stretching: Vec<f64>,
+    /// Normalizes the loudness of the source, nodes playing it connect here.
+    level: GainNode,
+    state: State,
+}
+
+impl TrackInternal {
+    fn new(context: &AudioContext, volume: &GainNode) -> Result<Self, Error> {
+        let level = context.create_gain().map_err(Error::audio)?;
+        level
+            .connect_with_audio_node(volume)
+            .map_err(Error::audio)?;
+        Ok(TrackInternal {
+            source: None,
+            stretched: vec![],
+            stretching: vec![],
+            level,
+            state: State::Loading,
+        })
+    }
+
+    /// Stops the track and takes it out of the audio graph.
+    fn remove(self) -> Result<(), Error> {
+        self.state.halt()?;
+        self.level.disconnect().map_err(Error::audio)
+    }
+
+    /// Switches to `state`, stopping the node playing before.
+    fn set_state(&mut self, state: State) -> Result<(), Error> {
+        mem::replace(&mut self.state, state).halt()
+    }
+
+    /// The source stretched to `rate` and `false`, or the source and `true`
+    /// if it still has to be sped up natively. Stretching starts in the
+    /// background the first time a rate is asked for.
+    fn buffer(&mut self, key: Key, rate: f64) -> Result<(AudioBuffer, bool), Error> {
+        let Some(source) = &self.source else {
+            return Err(Error::Audio("track is still loading".to_string()));
+        };
+        if rate == 1.0 {
+            return Ok((source.clone(), false));
+        }
+        if let Some((_, buffer)) = self.stretched.iter().find(|(r, _)| *r == rate) {
+            return Ok((buffer.clone(), false));
+        }
+        if !self.stretching.contains(&rate) {
+            self.stretching.push(rate);
+            spawn_local(async move {
+                let stretched = stretch(key, rate).await;
+                // done either way, a failed rate is tried again when asked for
+                let done = with_player(|p| {
+                    if let Some(t) = p.tracks.get_mut(key) {
+                        t.stretching.retain(|r| *r != rate);
+                    }
+                    Ok(())
+                });
+                if let Err(e) = stretched.and(done) {
+                    logging::warn!("{e}");
+                }
+            });
+        }
+        Ok((source.clone(), true))
+    }
+
+    /// Plays from `offset` at context time `when`, right away if it passed.
+    fn start(
+        &mut self,
+        key: Key,
+        context: &AudioContext,
+        rate: f64,
+        when: Option<f64>,
+        offset: f64,
+    ) -> Result<(), Error> {
+        self.set_state(State::Ready)?;
+        let level = self.level.clone();
+        let start = |(buffer, native): (AudioBuffer, bool)| -> Result<State, Error> {
+            let node = context.create_buffer_source().map_err(Error::audio)?;
+            node.set_buffer(Some(&buffer));
+            let speed = if native { rate } else { 1.0 };
+            node.playback_rate().set_value(speed as f32);
+            node.connect_with_audio_node(&level).map_err(Error::audio)?;
+            let start_time = when.unwrap_or(0.0).max(context.current_time());
+            node.start_with_when_and_grain_offset(start_time, offset * speed / rate)
+                .map_err(Error::audio)?;
+            // stopping removes the handler, so it only runs at the end
+            let ended = Closure::<dyn FnMut()>::new(move || {
+                let ended = with_player(|p| match p.tracks.get_mut(key) {
+                    Some(t) => match t.state {
+                        State::Playing {
+                            start_time, rate, ..
+                        } => t.set_state(State::Ended { start_time, rate }),
+                        _ => Ok(()),
+                    },
+                    None => Ok(()),
+                });
+                if let Err(e) = ended {
+                    logging::warn!("{e}");
+                }
+            });
+            node.set_onended(Some(ended.as_ref().unchecked_ref()));
+            Ok(State::Playing {
+                node,
+                ended,
+                start_time: start_time - offset / rate,
+                rate,
+                native,
+            })
+        };
+        match self.buffer(key, rate).and_then(start) {
+            Ok(playing) => self.set_state(playing),
+            Err(e) => {
+                self.set_state(State::Error(e.clone()))?;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Stretches the source of the track `key` to `rate` a part at a time, so
+/// the page stays responsive, and continues the track with the stretched
+/// audio if it plays sped up natively meanwhile.
+async fn stretch(key: Key, rate: f64) -> Result<(), Error> {
+    let source = with_player(|p| Ok(p.tracks.get_mut(key).and_then(|t| t.source.clone())))?;
+    let Some(source) = source else {
+        return Ok(());
+    };
+    let sample_rate = source.sample_rate();
+    let mut stretch = Stretch::new(samples(&source)?, sample_rate, rate);
+    while !stretch.advance((sample_rate * STRETCH_STEP) as usize) {
+        helper::sleep(Duration::ZERO).await;
+    }
+    let channels = stretch.finish();
+
+    with_player(|p| {
+        // removed while stretching
+        let Some(t) = p.tracks.get_mut(key) else {
+            return Ok(());
+        };
+        let length = channels.first().map_or(0, Vec::len);
+        let buffer = p
+            .context
+            .create_buffer(
+                source.number_of_channels(),
+                length.max(1) as u32,
+                sample_rate,
+            )
+            .map_err(Error::audio)?;
+        for (c, channel) in channels.iter().enumerate() {
+            buffer
+                .copy_to_channel(channel, c as i32)
+                .map_err(Error::audio)?;
+        }
+        t.stretched.push((rate, buffer));
+        match t.state {
+            State::Playing {
+                start_time,
+                rate: r,
+                native: true,
+                ..
+            } if r == rate => {
+                // where it is now, or from the start where it is scheduled
+                let offset = ((p.context.current_time() - start_time) * rate).max(0.0);
+                t.start(
+                    key,
+                    &p.context,
+                    rate,
+                    Some(start_time + offset / rate),
+                    offset,
+                )
+            }
+            _ => Ok(()),
+        }
+    })
+}
+
+/// Decodes an audio file with the context of the player.
+async fn decode(source: &[u8]) -> Result<AudioBuffer, Error> {
+    let uint8_array = Uint8Array::from(source);
+    let promise = with_player(|p| {
+        p.context
+            .decode_audio_data(&uint8_array.buffer())
+            .map_err(Error::audio)
+    })?;
+    let decode = |e| Error::decode("audio", error::message(e));
+    JsFuture::from(promise)
+        .await
+        .and_then(|b| b.dyn_into())
+        .map_err(decode)
+}
+
+/// Copies of the samples of each channel of `buffer`.
+fn samples(buffer: &AudioBuffer) -> Result<Vec<Vec<f32>>, Error> {
+    (0..buffer.number_of_channels())
+        .map(|c| buffer.get_channel_data(c).map_err(Error::audio))
+        .collect()
+}
+
+/// Way a track plays its audio, the methods are those of [`Track`].
+trait Backend {
+    fn prepare(&self) -> Result<(), Error>;
+    fn play(&self) -> Result<(), Error>;
+    fn play_at(&self, offset: f64) -> Result<(), Error>;
+    /// Plays from the start at context time `when`.
+    fn play_after(&self, when: f64) -> Result<(), Error>;
+    /// Context time at which `position` seconds of the audio play.
+    fn time_at(&self, position: f64) -> Option<f64>;
+    fn pause(&self) -> Result<(), Error>;
+    fn stop(&self) -> Result<(), Error>;
+    fn progress(&self) -> Option<f64>;
+    fn set_rate(&self, rate: f64) -> Result<(), Error>;
+    fn take_error(&self) -> Option<Error>;
+}
+
+/// Audio of a segment, either decoded whole or streamed.
+#[derive(Clone)]
+pub struct Track {
+    inner: Arc<dyn Backend + Send + Sync>,
+}
+
+impl Track {
+    pub async fn new(source: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            inner: Arc::new(TrackInner::new(source).await?),
+        })
+    }
+
+    fn from_buffer(buffer: AudioBuffer) -> Result<Self, Error> {
+        Ok(Self {
+            inner: Arc::new(TrackInner::from_buffer(buffer)?),
+        })
+    }
+
+    /// Streams `start..end` seconds of the file at `url`, to its end without
+    /// `end`.
+    pub fn stream(url: &str, start: f64, end: Option<f64>) -> Result<Self, Error> {
+        Ok(Self {
+            inner: Arc::new(StreamInner::new(url, start, end)?),
+        })
+    }
+
+    /// Gets the audio ready to play at the current rate, decoded audio starts
+    /// stretching and streamed audio buffering.
+    pub fn prepare(&self) -> Result<(), Error> {
+        self.inner.prepare()
+    }
+
+    pub fn play(&self) -> Result<(), Error> {
+        self.inner.play()
+    }
+
+    pub fn play_at(&self, offset: f64) -> Result<(), Error> {
+        self.inner.play_at(offset)
+    }
+
+    /// Plays from the start once `previous` has played `position` seconds of
+    /// its audio, so both play back to back on the same clock. Does nothing
+    /// if `previous` isn't playing or this track already started.
+    pub fn play_after(&self, previous: &Track, position: f64) -> Result<(), Error> {
+        match previous.inner.time_at(position) {
+            Some(when) => self.inner.play_after(when),
+            None => Ok(()),
+        }
+    }
+
+    pub fn pause(&self) -> Result<(), Error> {
+        self.inner.pause()
+    }
+
+    pub fn stop(&self) -> Result<(), Error> {
+        self.inner.stop()
+    }
+
+    /// Position in the audio in seconds, independent of the rate.
+    pub fn progress(&self) -> Option<f64> {
+        self.inner.progress()
+    }
+
+    /// Sets the playback rate of all tracks, a playing track continues at
+    /// the new rate.
+    pub fn set_rate(&self, rate: f64) -> Result<(), Error> {
+        self.inner.set_rate(rate)
+    }
+
+    /// Failure after the call starting to play returned, like the browser
+    /// refusing to play a stream. Returned once, the track stops playing.
+    pub fn take_error(&self) -> Option<Error> {
+        self.inner.take_error()
+    }
+}
+
+impl PartialEq for Track {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+struct TrackInner {
+    key: Key,
+}
+
+impl TrackInner {
+    async fn new(source: &[u8]) -> Result<Self, Error> {
+        // registered while loading, dropping it on failure removes it again
+        let track = Self::register()?;
+        track.load(decode(source).await?)?;
+        Ok(track)
+    }
+
+    fn from_buffer(buffer: AudioBuffer) -> Result<Self, Error> {
+        let track = Self::register()?;
+        track.load(buffer)?;
+        Ok(track)
+    }
+
+    fn register() -> Result<Self, Error> {
+        Ok(Self {
+            key: with_player(|p| {
+                let track = TrackInternal::new(&p.context, &p.volume)?;
+                Ok(p.tracks.insert(track))
+            })?,
+        })
+    }
+
+    /// Sets the decoded source, normalizing its loudness.
+    fn load(&self, buffer: AudioBuffer) -> Result<(), Error> {
+        let gain = loudness::normalization(&samples(&buffer)?, buffer.sample_rate());
+        self.with_track(|t, _, _| {
+            t.level.gain().set_value(gain);
+            t.source = Some(buffer);
+            t.set_state(State::Ready)
+        })
+    }
+
+    /// Runs `f` with the internal track, the context and the rate of the
+    /// player.
+    fn with_track<T, R>(&self, f: T) -> Result<R, Error>
+    where
+        T: FnOnce(&mut TrackInternal, &AudioContext, f64) -> Result<R, Error>,
+    {
+        with_player(|p| {
+            let track = p
+                .tracks
+                .get_mut(self.key)
+                .ok_or_else(|| Error::Audio("track was removed".to_string()))?;
+            f(track, &p.context, p.rate)
+        })
+    }
+
+    fn is_playing(&self) -> bool {
+        self.with_track(|t, _, _| Ok(matches!(t.state, State::Playing { .. })))
+            .unwrap_or(false)
+    }
+
+    /// Starts playing at context time `when`, right away if it passed, from
+    /// `offset` or where it was paused.
+    fn internal_play(&self, when: Option<f64>, offset: Option<f64>) -> Result<(), Error> {
+        let key = self.key;
+        self.with_track(|t, c, rate| {
+            let offset = offset.unwrap_or(match t.state {
+                State::Paused { offset } => offset,
+                _ => 0.0,
+            });
+            t.start(key, c, rate, when, offset)
+        })
+    }
+}
+
+impl Backend for TrackInner {
+    fn prepare(&self) -> Result<(), Error> {
+        let key = self.key;
+        self.with_track(|t, _, rate| t.buffer(key, rate).map(|_| ()))
+    }
+
+    fn play(&self) -> Result<(), Error> {
+        // a track scheduled to follow another keeps its schedule, one that
+        // ended keeps its clock
+        let running = self.with_track(|t, _, _| {
+            Ok(matches!(
+                t.state,
+                State::Playing { .. } | State::Ended { .. }
+            ))
+        })?;
+        if !running {
+            self.internal_play(None, None)?;
+        }
+        Ok(())
+    }
+
+    fn play_at(&self, offset: f64) -> Result<(), Error> {
+        self.internal_play(None, Some(offset))
+    }
+
+    fn play_after(&self, when: f64) -> Result<(), Error> {
+        let scheduled = self.with_track(|t, c, _| {
+            Ok(match t.state {
+                // already audible or scheduled for the same time
+                State::Playing { start_time, .. } => {
+                    start_time <= c.current_time() || (start_time - when).abs() < 1e-3
+                }
+                _ => false,
+            })
+        })?;
+        if scheduled {
+            return Ok(());
+        }
+        self.internal_play(Some(when), Some(0.0))
+    }
+
+    fn time_at(&self, position: f64) -> Option<f64> {
+        self.with_track(|t, _, _| {
+            Ok(match t.state {
+                State::Playing {
+                    start_time, rate, ..
+                }
+                | State::Ended { start_time, rate } => Some(start_time + position / rate),
+                _ => None,
+            })
+        })
+        .ok()
+        .flatten()
+    }
+
+    fn pause(&self) -> Result<(), Error> {
+        self.with_track(|t, c, _| match t.state.progress(c.current_time()) {
+            // a scheduled track may not have started yet
+            Some(offset) => t.set_state(State::Paused {
+                offset: offset.max(0.0),
+            }),
+            None => Ok(()),
+        })
+    }
+
+    fn stop(&self) -> Result<(), Error> {
+        self.with_track(|t, _, _| match t.state {
+            State::Loading => Ok(()),
+            _ => t.set_state(State::Ready),
+        })
+    }
+
+    fn progress(&self) -> Option<f64> {
+        self.with_track(|t, c, _| Ok(t.state.progress(c.current_time())))
+            .ok()
+            .flatten()
+    }
+
+    fn set_rate(&self, rate: f64) -> Result<(), Error> {
+        let changed = with_player(|p| {
+            let changed = p.rate != rate;
+            p.rate = rate;
+            Ok(changed)
+        })?;
+        if changed && self.is_playing() {
+            self.pause()?;
+            self.play()?;
+        }
+        Ok(())
+    }
+
+    fn take_error(&self) -> Option<Error> {
+        // failures starting a node are returned right away
+        None
+    }
+}
+
+impl Drop for TrackInner {
+    fn drop(&mut self) {
+        let removed = with_player(|p| match p.tracks.remove(self.key) {
+            Some(t) => t.remove(),
+            None => Ok(()),
+        });
+        if let Err(e) = removed {
+            logging::warn!("{e}");
+        }
+    }
+}
+
+/// Audio file segments play windows of, decoded once or streamed.
+#[derive(Clone)]
+pub struct File {
+    inner: Arc<FileInner>,
+}
+
+impl File {
+    pub async fn new(source: &[u8]) -> Result<Self, Error> {
+        let buffer = decode(source).await?;
+        Ok(Self {
+            inner: Arc::new(FileInner::Decoded(with_player(|p| {
+                Ok(p.files.insert(buffer))
+            })?)),
+        })
+    }
+
+    /// File at `url` whose windows are streamed from it.
+    pub fn stream(url: &str) -> Self {
+        Self {
+            inner: Arc::new(FileInner::Streamed(url.to_string())),
+        }
+    }
+
+    /// Track playing the file from `start` to `end` seconds. Decoded samples
+    /// are copied so the window plays, stretches and normalizes like any
+    /// other track.
+    pub fn window(&self, start: f64, end: f64) -> Result<Track, Error> {
+        let key = match &*self.inner {
+            FileInner::Decoded(key) => *key,
+            FileInner::Streamed(url) => return Track::stream(url, start, Some(end)),
+        };
+        let buffer = with_player(|p| {
+            let file = p
+                .files
+                .get_mut(key)
+                .ok_or_else(|| Error::Audio("file was removed".to_string()))?;
+            let sample_rate = file.sample_rate();
+            let first = ((start.max(0.0) * sample_rate as f64) as u32).min(file.length());
+            let last = ((end * sample_rate as f64) as u32).clamp(first, file.length());
+            let buffer = p
+                .context
+                .create_buffer(
+                    file.number_of_channels(),
+                    (last - first).max(1),
+                    sample_rate,
+                )
+                .map_err(Error::audio)?;
+            let mut samples = vec![0.0; (last - first) as usize];
+            for c in 0..file.number_of_channels() as i32 {
+                file.copy_from_channel_with_start_in_channel(&mut samples, c, first)
+                    .map_err(Error::audio)?;
+                buffer.copy_to_channel(&samples, c).map_err(Error::audio)?;
+            }
+            Ok(buffer)
+        })?;
+        Track::from_buffer(buffer)
+    }
+}
+
+impl PartialEq for File {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+enum FileInner {
+    Decoded(Key),
+    Streamed(String),
+}
+
+impl Drop for FileInner {
+    fn drop(&mut self) {
+        if let FileInner::Decoded(key) = self
+            && let Err(e) = with_player(|p| Ok(p.files.remove(*key)))
+        {
+            logging::warn!("{e}");
+        }
+    }
+}
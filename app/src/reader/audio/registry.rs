@@ -0,0 +1,117 @@
+/// Key of a value in a [`Registry`], stale once the value is removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Key {
+    index: usize,
+    generation: u32,
+}
+
+/// Values in reusable slots, looked up by key in constant time. Slots count
+/// their generations so the key of a removed value never finds the value
+/// reusing its slot.
+#[derive(Debug)]
+pub(super) struct Registry<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+impl<T> Registry<T> {
+    pub(super) fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub(super) fn insert(&mut self, value: T) -> Key {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.value = Some(value);
+                Key {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                Key {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub(super) fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        self.slot(key)?.value.as_mut()
+    }
+
+    pub(super) fn remove(&mut self, key: Key) -> Option<T> {
+        let slot = self.slot(key)?;
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index);
+        Some(value)
+    }
+
+    fn slot(&mut self, key: Key) -> Option<&mut Slot<T>> {
+        self.slots
+            .get_mut(key.index)
+            .filter(|s| s.generation == key.generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_found_until_removed() {
+        let mut registry = Registry::new();
+        let a = registry.insert("a");
+        let b = registry.insert("b");
+        assert_eq!(registry.get_mut(a), Some(&mut "a"));
+        assert_eq!(registry.get_mut(b), Some(&mut "b"));
+        assert_eq!(registry.remove(a), Some("a"));
+        assert_eq!(registry.get_mut(a), None);
+        assert_eq!(registry.remove(a), None);
+        assert_eq!(registry.get_mut(b), Some(&mut "b"));
+    }
+
+    #[test]
+    fn stale_keys_miss_values_reusing_their_slot() {
+        let mut registry = Registry::new();
+        let old = registry.insert(1);
+        registry.remove(old);
+        let new = registry.insert(2);
+        assert_eq!(new.index, old.index);
+        assert_ne!(new, old);
+        assert_eq!(registry.get_mut(old), None);
+        assert_eq!(registry.remove(old), None);
+        assert_eq!(registry.get_mut(new), Some(&mut 2));
+    }
+
+    #[test]
+    fn removing_bumps_the_generation_once() {
+        let mut registry = Registry::new();
+        let key = registry.insert(());
+        assert_eq!(key.generation, 0);
+        registry.remove(key);
+        // removing again with the stale key changes nothing
+        registry.remove(key);
+        let key = registry.insert(());
+        assert_eq!(key.generation, 1);
+        registry.remove(key);
+        assert_eq!(registry.insert(()).generation, 2);
+        assert_eq!(registry.slots.len(), 1);
+    }
+}
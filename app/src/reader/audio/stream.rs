@@ -0,0 +1,358 @@
+//! Playback of long recordings with an audio element, which streams the file
+//! with range requests while playing instead of decoding it whole up front.
+
+use std::time::Duration;
+
+use leptos::{
+    logging,
+    prelude::{TimeoutHandle, set_timeout_with_handle},
+    task::spawn_local,
+};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    AudioContext, DomException, GainNode, HtmlAudioElement, MediaElementAudioSourceNode,
+};
+
+use super::{Backend, registry::Key, with_player};
+use crate::error::Error;
+
+/// What a stream is doing, times are context times, positions in seconds of
+/// the window.
+#[derive(Debug)]
+enum State {
+    Ready,
+    /// Playing, or waiting for `timer` to start playing at `start_time`.
+    Playing {
+        start_time: f64,
+        timer: Option<TimeoutHandle>,
+    },
+    Paused {
+        offset: f64,
+    },
+    /// Played to the end at `end_time`. The clock keeps running like for
+    /// decoded tracks, so pauses after the track can be timed by its progress.
+    Ended {
+        end_time: f64,
+        rate: f64,
+    },
+}
+
+#[derive(Debug)]
+pub(super) struct StreamInternal {
+    element: HtmlAudioElement,
+    node: MediaElementAudioSourceNode,
+    /// Part of the file played in seconds of the file, to the end of the file
+    /// without `end`.
+    start: f64,
+    end: Option<f64>,
+    state: State,
+    /// The browser refusing to play, kept until it is reported.
+    failed: Option<Error>,
+}
+
+impl StreamInternal {
+    fn new(
+        context: &AudioContext,
+        volume: &GainNode,
+        url: &str,
+        start: f64,
+        end: Option<f64>,
+    ) -> Result<Self, Error> {
+        let element = HtmlAudioElement::new_with_src(url).map_err(Error::audio)?;
+        element.set_cross_origin(Some("anonymous"));
+        element.set_preload("metadata");
+        // played through the context, so the volume applies like to all tracks
+        let node = context
+            .create_media_element_source(&element)
+            .map_err(Error::audio)?;
+        node.connect_with_audio_node(volume).map_err(Error::audio)?;
+        Ok(Self {
+            element,
+            node,
+            start,
+            end,
+            state: State::Ready,
+            failed: None,
+        })
+    }
+
+    fn length(&self) -> f64 {
+        self.end.unwrap_or(self.element.duration()) - self.start
+    }
+
+    /// Stops the element and a timer waiting to start it.
+    fn halt(&mut self) -> Result<(), Error> {
+        if let State::Playing {
+            timer: Some(timer), ..
+        } = self.state
+        {
+            timer.clear();
+        }
+        self.element.pause().map_err(Error::audio)
+    }
+
+    /// Plays from `offset` at context time `when`, right away if it passed.
+    fn start(
+        &mut self,
+        key: Key,
+        offset: f64,
+        when: Option<f64>,
+        now: f64,
+        rate: f64,
+    ) -> Result<(), Error> {
+        self.halt()?;
+        self.element.set_playback_rate(rate);
+        self.element.set_current_time(self.start + offset);
+        let delay = when.map_or(0.0, |w| w - now);
+        if delay <= 0.0 {
+            self.state = State::Playing {
+                start_time: now - offset / rate,
+                timer: None,
+            };
+            return play(key, &self.element);
+        }
+        let timer = set_timeout_with_handle(
+            move || {
+                let started = with_player(|p| match p.streams.get_mut(key) {
+                    Some(s) => match &mut s.state {
+                        State::Playing { timer, .. } if timer.is_some() => {
+                            *timer = None;
+                            play(key, &s.element)
+                        }
+                        _ => Ok(()),
+                    },
+                    None => Ok(()),
+                });
+                if let Err(e) = started {
+                    logging::warn!("{e}");
+                }
+            },
+            Duration::from_secs_f64(delay),
+        )
+        .map_err(Error::audio)?;
+        self.state = State::Playing {
+            start_time: now + delay,
+            timer: Some(timer),
+        };
+        Ok(())
+    }
+
+    /// Position in the window if the clock is running. The element doesn't
+    /// stop at the end of a window by itself, so reaching it is noticed here,
+    /// which the reader polls while playing, see [`super::publish_progress`].
+    fn progress(&mut self, now: f64, rate: f64) -> Result<Option<f64>, Error> {
+        Ok(match self.state {
+            State::Playing { start_time, .. } if now < start_time => {
+                Some((now - start_time) * rate)
+            }
+            State::Playing { .. } => {
+                let position = self.element.current_time() - self.start;
+                let length = self.length();
+                if self.element.ended() || position >= length {
+                    self.halt()?;
+                    self.state = State::Ended {
+                        end_time: now - (position - length).max(0.0) / rate,
+                        rate,
+                    };
+                }
+                Some(position)
+            }
+            State::Ended { end_time, rate } => Some(self.length() + (now - end_time) * rate),
+            _ => None,
+        })
+    }
+}
+
+/// Plays the element of the stream `key`. Playing may still be refused
+/// later, then the stream pauses and keeps the failure to report it.
+fn play(key: Key, element: &HtmlAudioElement) -> Result<(), Error> {
+    let promise = element.play().map_err(Error::audio)?;
+    spawn_local(async move {
+        let Err(e) = JsFuture::from(promise).await else {
+            return;
+        };
+        // pausing before playing started, which is no failure
+        if e.dyn_ref::<DomException>()
+            .is_some_and(|e| e.name() == "AbortError")
+        {
+            return;
+        }
+        let failed = with_player(|p| match p.streams.get_mut(key) {
+            Some(s) if matches!(s.state, State::Playing { .. }) => {
+                s.halt()?;
+                s.state = State::Paused {
+                    offset: (s.element.current_time() - s.start).max(0.0),
+                };
+                s.failed = Some(Error::audio(e));
+                Ok(())
+            }
+            _ => Ok(()),
+        });
+        if let Err(e) = failed {
+            logging::warn!("{e}");
+        }
+    });
+    Ok(())
+}
+
+pub(super) struct StreamInner {
+    key: Key,
+}
+
+impl StreamInner {
+    pub(super) fn new(url: &str, start: f64, end: Option<f64>) -> Result<Self, Error> {
+        Ok(Self {
+            key: with_player(|p| {
+                let stream = StreamInternal::new(&p.context, &p.volume, url, start, end)?;
+                Ok(p.streams.insert(stream))
+            })?,
+        })
+    }
+
+    /// Runs `f` with the internal stream, the time of the context and the
+    /// rate of the player.
+    fn with_stream<T, R>(&self, f: T) -> Result<R, Error>
+    where
+        T: FnOnce(&mut StreamInternal, f64, f64) -> Result<R, Error>,
+    {
+        with_player(|p| {
+            let stream = p
+                .streams
+                .get_mut(self.key)
+                .ok_or_else(|| Error::Audio("track was removed".to_string()))?;
+            f(stream, p.context.current_time(), p.rate)
+        })
+    }
+}
+
+impl Backend for StreamInner {
+    fn prepare(&self) -> Result<(), Error> {
+        // start buffering where playing will start
+        self.with_stream(|s, _, _| {
+            s.element.set_preload("auto");
+            if let State::Ready = s.state {
+                s.element.set_current_time(s.start);
+            }
+            Ok(())
+        })
+    }
+
+    fn play(&self) -> Result<(), Error> {
+        let key = self.key;
+        self.with_stream(|s, now, rate| {
+            let offset = match s.state {
+                // scheduled to follow another or keeping its clock
+                State::Playing { .. } | State::Ended { .. } => return Ok(()),
+                State::Paused { offset } => offset,
+                State::Ready => 0.0,
+            };
+            s.start(key, offset, None, now, rate)
+        })
+    }
+
+    fn play_at(&self, offset: f64) -> Result<(), Error> {
+        let key = self.key;
+        self.with_stream(|s, now, rate| s.start(key, offset, None, now, rate))
+    }
+
+    fn play_after(&self, when: f64) -> Result<(), Error> {
+        let key = self.key;
+        self.with_stream(|s, now, rate| match s.state {
+            // already audible or scheduled for the same time
+            State::Playing { start_time, .. }
+                if start_time <= now || (start_time - when).abs() < 1e-3 =>
+            {
+                Ok(())
+            }
+            _ => s.start(key, 0.0, Some(when), now, rate),
+        })
+    }
+
+    fn time_at(&self, position: f64) -> Option<f64> {
+        self.with_stream(|s, now, rate| {
+            Ok(match s.state {
+                State::Playing { start_time, .. } if now < start_time => {
+                    Some(start_time + position / rate)
+                }
+                State::Playing { .. } => {
+                    Some(now + (position - (s.element.current_time() - s.start)) / rate)
+                }
+                State::Ended { end_time, rate } => Some(end_time + (position - s.length()) / rate),
+                _ => None,
+            })
+        })
+        .ok()
+        .flatten()
+    }
+
+    fn pause(&self) -> Result<(), Error> {
+        self.with_stream(|s, now, rate| match s.progress(now, rate)? {
+            // a scheduled stream may not have started yet
+            Some(offset) => {
+                s.halt()?;
+                s.state = State::Paused {
+                    offset: offset.max(0.0),
+                };
+                Ok(())
+            }
+            None => Ok(()),
+        })
+    }
+
+    fn stop(&self) -> Result<(), Error> {
+        self.with_stream(|s, _, _| {
+            s.halt()?;
+            s.element.set_current_time(s.start);
+            s.state = State::Ready;
+            Ok(())
+        })
+    }
+
+    fn progress(&self) -> Option<f64> {
+        self.with_stream(|s, now, rate| s.progress(now, rate))
+            .ok()
+            .flatten()
+    }
+
+    fn set_rate(&self, rate: f64) -> Result<(), Error> {
+        with_player(|p| {
+            p.rate = rate;
+            if let Some(s) = p.streams.get_mut(self.key) {
+                // the element keeps the pitch by itself
+                s.element.set_playback_rate(rate);
+            }
+            Ok(())
+        })
+    }
+
+    fn take_error(&self) -> Option<Error> {
+        self.with_stream(|s, _, _| Ok(s.failed.take()))
+            .ok()
+            .flatten()
+    }
+}
+
+impl Drop for StreamInner {
+    fn drop(&mut self) {
+        let mut s = match with_player(|p| Ok(p.streams.remove(self.key))) {
+            Ok(Some(s)) => s,
+            Ok(None) => return,
+            Err(e) => {
+                logging::warn!("{e}");
+                return;
+            }
+        };
+        // every step runs even if one before failed
+        let cleanup = [
+            s.halt(),
+            s.node.disconnect().map_err(Error::audio),
+            // stop downloading
+            s.element.remove_attribute("src").map_err(Error::audio),
+        ];
+        s.element.load();
+        for e in cleanup.into_iter().filter_map(Result::err) {
+            logging::warn!("{e}");
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Time stretching with WSOLA (waveform similarity overlap-add), changes the
+//! speed of speech without changing its pitch.
+
+use std::f32::consts::PI;
+
+/// Length of the frames in seconds, long enough to hold a few pitch periods.
+const FRAME: f32 = 0.02;
+/// How far a frame may be moved to continue the waveform of the one before.
+const TOLERANCE: f32 = 0.005;
+
+/// Stretching of audio to play `rate` times as fast, done a part at a time
+/// with [`Stretch::advance`] so long audio doesn't block the page. All
+/// channels have the same length and are stretched alike.
+pub(super) struct Stretch {
+    channels: Vec<Vec<f32>>,
+    /// Channels mixed down to find similar frames on.
+    mix: Vec<f32>,
+    window: Vec<f32>,
+    rate: f64,
+    frame: usize,
+    hop: usize,
+    tolerance: usize,
+    output: Vec<Vec<f32>>,
+    weight: Vec<f32>,
+    output_length: usize,
+    /// Next frame to add and the start of the frame added before it.
+    next: usize,
+    previous: usize,
+}
+
+impl Stretch {
+    pub(super) fn new(channels: Vec<Vec<f32>>, sample_rate: f32, rate: f64) -> Self {
+        let length = channels.first().map_or(0, Vec::len);
+        let frame = ((sample_rate * FRAME) as usize).max(4) & !1;
+        let mut output_length = (length as f64 / rate) as usize;
+        if length < frame {
+            // too short to stretch, finished right away
+            output_length = 0;
+        }
+
+        let window = (0..frame)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / frame as f32).cos())
+            .collect();
+        let mix = match output_length {
+            0 => vec![],
+            _ => (0..length)
+                .map(|i| channels.iter().map(|c| c[i]).sum::<f32>() / channels.len() as f32)
+                .collect(),
+        };
+        let padded = match output_length {
+            0 => 0,
+            _ => output_length + frame,
+        };
+        Self {
+            output: vec![vec![0.0; padded]; channels.len()],
+            weight: vec![0.0; padded],
+            channels,
+            mix,
+            window,
+            rate,
+            frame,
+            hop: frame / 2,
+            tolerance: (sample_rate * TOLERANCE) as usize,
+            output_length,
+            next: 0,
+            previous: 0,
+        }
+    }
+
+    /// Adds frames for about `samples` more samples of output, returns
+    /// whether all are added.
+    pub(super) fn advance(&mut self, samples: usize) -> bool {
+        if self.output_length == 0 {
+            return true;
+        }
+        let (frame, hop) = (self.frame, self.hop);
+        let frames = self.output_length / hop + 1;
+        let until = (self.next + samples.div_ceil(hop)).min(frames);
+        let last = self.mix.len().saturating_sub(frame);
+        for k in self.next..until {
+            let position = k * hop;
+            let nominal = ((position as f64 * self.rate) as usize).min(last);
+            let natural = self.previous + hop;
+            let start = if k == 0 || natural > last {
+                nominal
+            } else {
+                // coarse search first, then refine around the best match
+                let from = nominal.saturating_sub(self.tolerance);
+                let to = (nominal + self.tolerance).min(last);
+                let best = most_similar(&self.mix, (from..=to).step_by(4), natural, frame);
+                let best = best.unwrap_or(nominal);
+                let from = best.saturating_sub(3).max(from);
+                let to = (best + 3).min(to);
+                most_similar(&self.mix, from..=to, natural, frame).unwrap_or(best)
+            };
+            for (channel, out) in self.channels.iter().zip(&mut self.output) {
+                for n in 0..frame {
+                    out[position + n] += channel[start + n] * self.window[n];
+                }
+            }
+            for n in 0..frame {
+                self.weight[position + n] += self.window[n];
+            }
+            self.previous = start;
+        }
+        self.next = until;
+        until == frames
+    }
+
+    /// The stretched channels, the input as it was if it was too short.
+    pub(super) fn finish(mut self) -> Vec<Vec<f32>> {
+        if self.output_length == 0 {
+            return self.channels;
+        }
+        for out in &mut self.output {
+            for (sample, w) in out.iter_mut().zip(&self.weight) {
+                if *w > 1e-3 {
+                    *sample /= w;
+                }
+            }
+            out.truncate(self.output_length);
+        }
+        self.output
+    }
+}
+
+/// The candidate frame start most similar to the frame at `target`.
+fn most_similar(
+    samples: &[f32],
+    candidates: impl Iterator<Item = usize>,
+    target: usize,
+    frame: usize,
+) -> Option<usize> {
+    candidates
+        .map(|c| (c, similarity(samples, c, target, frame)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(c, _)| c)
+}
+
+/// Cross correlation of the frames at `a` and `b`, every other sample is
+/// enough to compare them.
+fn similarity(samples: &[f32], a: usize, b: usize, frame: usize) -> f32 {
+    (0..frame)
+        .step_by(2)
+        .map(|n| samples[a + n] * samples[b + n])
+        .sum()
+}
@@ -0,0 +1,97 @@
+use std::ops::Range;
+
+use common::{Block, BlockSpan, Segment, annotation::Annotation};
+use leptos::prelude::*;
+use leptos_mview::mview;
+
+use super::segment;
+
+/// Blocks of a text with consecutive list items of the same kind joined.
+enum Group {
+    Block(Block, Range<usize>),
+    List {
+        ordered: bool,
+        items: Vec<Range<usize>>,
+    },
+}
+
+fn group(blocks: Vec<BlockSpan>) -> Vec<Group> {
+    let mut groups = vec![];
+    for span in blocks {
+        match (span.block, groups.last_mut()) {
+            (Block::ListItem { ordered }, Some(Group::List { ordered: o, items }))
+                if ordered == *o =>
+            {
+                items.push(span.segments)
+            }
+            (Block::ListItem { ordered }, _) => groups.push(Group::List {
+                ordered,
+                items: vec![span.segments],
+            }),
+            (block, _) => groups.push(Group::Block(block, span.segments)),
+        }
+    }
+    groups
+}
+
+#[component]
+pub(crate) fn Blocks(
+    #[prop(into)] segments: Signal<Vec<Segment>>,
+    #[prop(into)] active: Signal<usize>,
+    #[prop(into)] highlight: Signal<Option<u32>>,
+    /// Set to the segment and word index of a clicked word.
+    #[prop(into)]
+    seek: RwSignal<Option<(usize, u32)>>,
+    #[prop(into)] annotations: Signal<Vec<Annotation>>,
+) -> impl IntoView {
+    move || {
+        let segments = segments.get();
+        let active = active.get();
+        let content = |range: Range<usize>| {
+            range
+                .map(|i| {
+                    mview! {
+                        segment::Segment
+                            segment={segments[i].clone()}
+                            index={i}
+                            active={active == i}
+                            {highlight}
+                            {seek}
+                            {annotations};
+                    }
+                })
+                .collect_view()
+        };
+        group(common::blocks(&segments))
+            .into_iter()
+            .map(|g| match g {
+                Group::Block(Block::Heading(level), range) => {
+                    let content = content(range);
+                    match level {
+                        0 | 1 => mview! { h1 { {content} } }.into_any(),
+                        2 => mview! { h2 { {content} } }.into_any(),
+                        3 => mview! { h3 { {content} } }.into_any(),
+                        4 => mview! { h4 { {content} } }.into_any(),
+                        5 => mview! { h5 { {content} } }.into_any(),
+                        _ => mview! { h6 { {content} } }.into_any(),
+                    }
+                }
+                Group::Block(Block::Quote, range) => {
+                    mview! { blockquote { p { {content(range)} } } }.into_any()
+                }
+                Group::Block(_, range) => mview! { p { {content(range)} } }.into_any(),
+                Group::List { ordered, items } => {
+                    let items = items
+                        .into_iter()
+                        .map(|range| mview! { li { {content(range)} } })
+                        .collect_view();
+                    if ordered {
+                        mview! { ol { {items} } }.into_any()
+                    } else {
+                        mview! { ul { {items} } }.into_any()
+                    }
+                }
+            })
+            .collect_view()
+    }
+}
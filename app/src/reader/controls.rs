@@ -1,19 +1,40 @@
 use icondata as icons;
-use leptos::prelude::*;
+use leptos::{ev, prelude::*};
 use leptos_icons::Icon;
 use leptos_mview::mview;
 
+use crate::helper;
+
+use super::{
+    audio::RATES,
+    repeat::{Mode, RepeatButtons},
+};
+
+/// Pauses between segments in seconds selectable in the reader.
+const PAUSES: [f64; 5] = [0.0, 0.3, 0.6, 1.0, 2.0];
+pub(crate) const DEFAULT_PAUSE: f64 = 0.3;
+
 #[component]
 pub(crate) fn Controls(
     #[prop(into)] page: RwSignal<usize>,
     #[prop(into)] playing: RwSignal<bool>,
     #[prop(into)] content_length: Signal<u32>,
     #[prop(into)] progress: RwSignal<Option<u32>>,
+    #[prop(into)] rate: RwSignal<f64>,
+    #[prop(into)] pause: RwSignal<f64>,
+    #[prop(into)] repeat: RwSignal<Mode>,
+    #[prop(into)] repeat_times: RwSignal<Option<u32>>,
+    #[prop(into)] repeat_gap: RwSignal<f64>,
+    #[prop(into)] volume: RwSignal<f64>,
+    #[prop(into)] muted: RwSignal<bool>,
+    #[prop(into)] annotations_open: RwSignal<bool>,
+    #[prop(into)] help_open: RwSignal<bool>,
 ) -> impl IntoView {
     mview! {
         div.controls {
             Progress {progress} {content_length};
-            Bar {playing} {page};
+            Bar {playing} {page} {progress} {rate} {pause} {repeat} {repeat_times} {repeat_gap}
+                {volume} {muted} {annotations_open} {help_open};
         }
     }
 }
@@ -46,13 +67,46 @@ fn Progress(
     }
 }
 
+/// Bar filling up while the audio of the current segment downloads, full if
+/// its size is unknown.
+#[component]
+pub(crate) fn Loading(#[prop(into)] progress: Signal<Option<helper::Progress>>) -> impl IntoView {
+    let fraction = move || progress.get().and_then(|p| p.fraction());
+    mview! {
+        div.loading class:open={move || progress.with(Option::is_some)} {
+            div.loaded
+                class:unknown={move || fraction().is_none()}
+                style:width={move || format!("{}%", fraction().unwrap_or(1.0) * 100.0)};
+        }
+    }
+}
+
 #[component]
 fn Bar(
     #[prop(into)] page: RwSignal<usize>,
     #[prop(into)] playing: RwSignal<bool>,
+    #[prop(into)] progress: RwSignal<Option<u32>>,
+    #[prop(into)] rate: RwSignal<f64>,
+    #[prop(into)] pause: RwSignal<f64>,
+    #[prop(into)] repeat: RwSignal<Mode>,
+    #[prop(into)] repeat_times: RwSignal<Option<u32>>,
+    #[prop(into)] repeat_gap: RwSignal<f64>,
+    #[prop(into)] volume: RwSignal<f64>,
+    #[prop(into)] muted: RwSignal<bool>,
+    #[prop(into)] annotations_open: RwSignal<bool>,
+    #[prop(into)] help_open: RwSignal<bool>,
 ) -> impl IntoView {
     mview! {
         div.bar {
+            button title="start over" {
+                Icon
+                    icon={icons::FaRotateLeftSolid}
+                    on:click={move |_| {
+                        playing.set(false);
+                        page.set(usize::MIN);
+                        progress.set(None);
+                    }};
+            }
             button {
                 Icon
                     icon={icons::FaBackwardFastSolid}
@@ -78,6 +132,82 @@ fn Bar(
                         |n| if *n < usize::MAX - 5 { *n += 5 } else { *n = usize::MAX }
                     )};
             }
+            RateButton {rate};
+            PauseButton {pause};
+            RepeatButtons mode={repeat} times={repeat_times} gap={repeat_gap};
+            Volume {volume} {muted};
+            button title="bookmarks and highlights" {
+                Icon
+                    icon={icons::FaBookmarkSolid}
+                    on:click={move |_| annotations_open.update(|o| *o = !*o)};
+            }
+            button title="keyboard shortcuts" {
+                Icon
+                    icon={icons::FaKeyboardSolid}
+                    on:click={move |_| help_open.update(|o| *o = !*o)};
+            }
+        }
+    }
+}
+
+#[component]
+fn RateButton(#[prop(into)] rate: RwSignal<f64>) -> impl IntoView {
+    // cycle through the rates, starting over after the fastest
+    let next =
+        move |_| rate.update(|r| *r = RATES.iter().copied().find(|n| n > r).unwrap_or(RATES[0]));
+    mview! {
+        button.rate on:click={next} {
+            { move || format!("{}×", rate.get()) }
+        }
+    }
+}
+
+#[component]
+fn PauseButton(#[prop(into)] pause: RwSignal<f64>) -> impl IntoView {
+    // cycle through the pauses between segments
+    let next =
+        move |_| pause.update(|p| *p = PAUSES.iter().copied().find(|n| n > p).unwrap_or(PAUSES[0]));
+    mview! {
+        button.pause title="pause between sentences" on:click={next} {
+            { move || format!("+{}s", pause.get()) }
+        }
+    }
+}
+
+#[component]
+fn Volume(
+    #[prop(into)] volume: RwSignal<f64>,
+    #[prop(into)] muted: RwSignal<bool>,
+) -> impl IntoView {
+    let icon = move || {
+        if muted.get() || volume.get() == 0.0 {
+            icons::FaVolumeXmarkSolid
+        } else if volume.get() < 0.5 {
+            icons::FaVolumeLowSolid
+        } else {
+            icons::FaVolumeHighSolid
+        }
+    };
+    // moving the slider unmutes
+    let set = move |e: ev::Event| {
+        if let Ok(v) = event_target_value(&e).parse() {
+            volume.set(v);
+            muted.set(false);
+        }
+    };
+
+    mview! {
+        div.volume {
+            button title="mute" on:click={move |_| muted.update(|m| *m = !*m)} {
+                Icon icon={Signal::derive(icon)};
+            }
+            input
+                type="range"
+                min="0"
+                max="1"
+                step="0.05"
+                prop:value={move || volume.get()}
+                on:input={set};
         }
     }
 }
@@ -0,0 +1,122 @@
+use std::cell::RefCell;
+
+use wasm_bindgen::{JsCast, prelude::Closure};
+use web_sys::{
+    MediaImage, MediaMetadata, MediaMetadataInit, MediaPositionState, MediaSession,
+    MediaSessionAction, MediaSessionActionDetails, MediaSessionPlaybackState, js_sys::Array,
+    window,
+};
+
+/// Control of the reader by the operating system, e.g. from the lock screen
+/// or the buttons of a headset.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Action {
+    Play,
+    Pause,
+    Stop,
+    Previous,
+    Next,
+    /// Go to a position in the current segment in seconds.
+    SeekTo(f64),
+}
+
+const ACTIONS: [MediaSessionAction; 6] = [
+    MediaSessionAction::Play,
+    MediaSessionAction::Pause,
+    MediaSessionAction::Stop,
+    MediaSessionAction::Previoustrack,
+    MediaSessionAction::Nexttrack,
+    MediaSessionAction::Seekto,
+];
+
+type Handler = Closure<dyn Fn(MediaSessionActionDetails)>;
+
+thread_local! {
+    /// Handlers set on the session, kept until [`disconnect`] removes them.
+    static HANDLERS: RefCell<Vec<Handler>> = const { RefCell::new(vec![]) };
+}
+
+fn session() -> Option<MediaSession> {
+    Some(window()?.navigator().media_session())
+}
+
+/// Passes the media session actions to `handle` until [`disconnect`].
+pub(crate) fn connect(handle: impl Fn(Action) + Clone + 'static) {
+    let Some(session) = session() else {
+        return;
+    };
+    let mut handlers = Vec::with_capacity(ACTIONS.len());
+    for action in ACTIONS {
+        let handle = handle.clone();
+        let handler = Handler::new(move |details: MediaSessionActionDetails| {
+            handle(match action {
+                MediaSessionAction::Play => Action::Play,
+                MediaSessionAction::Pause => Action::Pause,
+                MediaSessionAction::Stop => Action::Stop,
+                MediaSessionAction::Previoustrack => Action::Previous,
+                MediaSessionAction::Nexttrack => Action::Next,
+                _ => Action::SeekTo(details.get_seek_time().unwrap_or(0.0)),
+            })
+        });
+        session.set_action_handler(action, Some(handler.as_ref().unchecked_ref()));
+        handlers.push(handler);
+    }
+    // handlers of an earlier connect were replaced above
+    HANDLERS.with_borrow_mut(|h| *h = handlers);
+}
+
+/// Removes the action handlers and what the session shows.
+pub(crate) fn disconnect() {
+    let Some(session) = session() else {
+        return;
+    };
+    for action in ACTIONS {
+        session.set_action_handler(action, None);
+    }
+    HANDLERS.with_borrow_mut(Vec::clear);
+    session.set_metadata(None);
+    session.set_playback_state(MediaSessionPlaybackState::None);
+}
+
+/// Shows the segment playing like the title of a track of the text.
+pub(crate) fn set_metadata(segment: &str, title: &str, author: Option<&str>, cover: Option<&str>) {
+    let Some(session) = session() else {
+        return;
+    };
+    let init = MediaMetadataInit::new();
+    init.set_title(segment);
+    init.set_album(title);
+    if let Some(author) = author {
+        init.set_artist(author);
+    }
+    if let Some(cover) = cover {
+        init.set_artwork(&Array::of1(&MediaImage::new(cover)));
+    }
+    session.set_metadata(MediaMetadata::new_with_init(&init).ok().as_ref());
+}
+
+pub(crate) fn set_playing(playing: bool) {
+    if let Some(session) = session() {
+        session.set_playback_state(if playing {
+            MediaSessionPlaybackState::Playing
+        } else {
+            MediaSessionPlaybackState::Paused
+        });
+    }
+}
+
+/// Sets the position in the segment, both in seconds of its audio.
+pub(crate) fn set_position(duration: f64, position: f64, rate: f64) {
+    let Some(session) = session() else {
+        return;
+    };
+    if duration <= 0.0 {
+        _ = session.set_position_state();
+        return;
+    }
+    let state = MediaPositionState::new();
+    state.set_duration(duration);
+    state.set_position(position.clamp(0.0, duration));
+    state.set_playback_rate(rate);
+    _ = session.set_position_state_with_state(&state);
+}
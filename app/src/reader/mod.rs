@@ -1,22 +1,44 @@
-use std::usize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+    usize,
+};
 
 use audio::Track;
-use common::{Segment, Text, Wav, Word};
-use leptos::{logging, prelude::*};
+use common::{Playback, Position, Segment, Text, Timeline, Word};
+use leptos::{ev, logging, prelude::*, task::spawn_local};
+use leptos_meta::Title;
 use leptos_mview::mview;
-use leptos_use::{UseIntervalReturn, use_interval};
+use shortcuts::Action;
 
-mod helper;
+use crate::{
+    error::{Error, ErrorMessage},
+    helper, library, position,
+    shell::DEFAULT_LANGUAGE,
+};
 
+mod annotations;
 mod audio;
 
+mod block;
 mod controls;
+mod media_session;
+mod repeat;
 mod segment;
+mod shortcuts;
+
+/// Files larger than this in bytes are streamed unless the text says how to
+/// play them.
+const STREAM_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Local storage key of the volume and whether it is muted.
+const VOLUME_KEY: &str = "literacy.volume";
 
 #[component]
-pub(crate) fn Reader() -> impl IntoView {
+pub(crate) fn Reader(text_id: String) -> impl IntoView {
+    let base = library::text_base(&text_id);
     let text: RwSignal<Option<Text>> = RwSignal::new(None);
-    let segments_content: RwSignal<Vec<Vec<String>>> = RwSignal::new(vec![]);
+    let segments_content: RwSignal<Vec<Segment>> = RwSignal::new(vec![]);
     let segment_index = RwSignal::new(0);
     let segment: RwSignal<Option<Segment>> = RwSignal::new(None);
     let words: RwSignal<Vec<Word>> = RwSignal::new(vec![]);
@@ -27,55 +49,319 @@ pub(crate) fn Reader() -> impl IntoView {
     let audio: RwSignal<Option<Track>> = RwSignal::new(None);
     let audio_progress: RwSignal<Option<f64>> = RwSignal::new(None);
     let playing = RwSignal::new(false);
+    let seek: RwSignal<Option<(usize, u32)>> = RwSignal::new(None);
+    let rate = RwSignal::new(1.0);
+    let pause = RwSignal::new(controls::DEFAULT_PAUSE);
+    let (saved_volume, saved_muted) = helper::load_local(VOLUME_KEY).unwrap_or((1.0, false));
+    let volume = RwSignal::new(saved_volume);
+    let muted = RwSignal::new(saved_muted);
+    let tracks: RwSignal<BTreeMap<usize, Track>> = RwSignal::new(BTreeMap::new());
+    let loading: StoredValue<BTreeSet<usize>> = StoredValue::new(BTreeSet::new());
+    let previous_audio: StoredValue<Option<Track>> = StoredValue::new(None);
+    let restored = RwSignal::new(false);
+    let restore: StoredValue<Option<(usize, u32)>> = StoredValue::new(None);
+    let saved_segment: StoredValue<Option<usize>> = StoredValue::new(None);
+    let jump: RwSignal<Option<(usize, u32)>> = RwSignal::new(None);
+    let annotations = RwSignal::new(annotations::load(&text_id));
+    let annotations_open = RwSignal::new(false);
+    let repeat = RwSignal::new(repeat::Mode::Off);
+    let repeat_times: RwSignal<Option<u32>> = RwSignal::new(None);
+    let repeat_gap = RwSignal::new(repeat::DEFAULT_GAP);
+    // segment the last repetition ended in and how many have been played
+    let repeated: StoredValue<(usize, u32)> = StoredValue::new((usize::MAX, 0));
+    let repeat_waiting = StoredValue::new(false);
+    let bindings = RwSignal::new(shortcuts::Bindings::load());
+    let rebinding: RwSignal<Option<Action>> = RwSignal::new(None);
+    let help_open = RwSignal::new(false);
+    let text_error: RwSignal<Option<Error>> = RwSignal::new(None);
+    let track_errors: RwSignal<BTreeMap<usize, Error>> = RwSignal::new(BTreeMap::new());
+    let playback_error: RwSignal<Option<Error>> = RwSignal::new(None);
+    let downloads: RwSignal<BTreeMap<usize, helper::Progress>> = RwSignal::new(BTreeMap::new());
+    // shared files segments play windows of, by url
+    let files: RwSignal<BTreeMap<String, Result<audio::File, Error>>> =
+        RwSignal::new(BTreeMap::new());
+    let loading_files: StoredValue<BTreeSet<String>> = StoredValue::new(BTreeSet::new());
 
-    let text_resource: LocalResource<Text> = LocalResource::new(async move || {
-        serde_json::from_str(helper::load_text("./text.json").await.as_str())
-            .expect("expected segment as json")
-    });
-    let audio_resource = LocalResource::new(move || {
-        (async move |segment: Option<Segment>| {
-            if let Some(segment) = segment {
-                let bytes = match segment.audio {
-                    common::Audio::None => vec![],
-                    common::Audio::Wav(Wav::Raw(bytes)) => bytes,
-                    common::Audio::Wav(_) => todo!(),
-                    common::Audio::Ref(url) => helper::load_bytes(&url).await,
-                };
-                Some(Track::new(&bytes).await)
+    let text_url = helper::resolve(&base, "text.json");
+    let text_resource: LocalResource<Result<Text, Error>> = LocalResource::new(move || {
+        let text_url = text_url.clone();
+        async move {
+            let json = helper::load_text(&text_url).await?;
+            common::migrate::from_str(&json).map_err(|e| Error::decode("text document", e))
+        }
+    });
+
+    // show failures of the audio instead of stopping the reader
+    let report = move |result: Result<(), Error>| {
+        if let Err(e) = result {
+            logging::error!("{e}");
+            playback_error.set(Some(e));
+        }
+    };
+
+    // error of the text or the current segment, retrying loads it again
+    let error = Signal::derive(move || {
+        text_error
+            .get()
+            .or_else(|| track_errors.with(|e| e.get(&segment_index.get()).cloned()))
+            .or_else(|| playback_error.get())
+    });
+    let retry = move || {
+        if text_error.get_untracked().is_some() {
+            text_error.set(None);
+            text_resource.refetch();
+        }
+        files.update(|f| f.retain(|_, f| f.is_ok()));
+        track_errors.set(BTreeMap::new());
+        playback_error.set(None);
+    };
+
+    // load text from text resource
+    Effect::new(move || match text_resource.get().map(|t| t.take()) {
+        Some(Ok(mut t)) => {
+            for d in common::validate(&t) {
+                logging::warn!("{d}");
+            }
+            for segment in &mut t.segments {
+                segment.make_timestamps_relative();
+            }
+            text.set(Some(t));
+        }
+        Some(Err(e)) => {
+            logging::error!("{e}");
+            text_error.set(Some(e));
+        }
+        None => {}
+    });
+
+    // go to a word without playing
+    let go_to = move |index: usize, word: Option<u32>| {
+        playing.set(false);
+        if index == segment_index.get_untracked() {
+            progress.set(word);
+        } else {
+            restore.set_value(word.map(|w| (index, w)));
+            segment_index.set(index);
+        }
+    };
+
+    // restore the reading position, the more recent one of the browser and
+    // the server
+    let position_id = text_id.clone();
+    Effect::new(move |started: Option<bool>| {
+        if started == Some(true) || text.with(Option::is_none) {
+            return started.unwrap_or(false);
+        }
+        let id = position_id.clone();
+        spawn_local(async move {
+            let local = position::load_local(&id);
+            let remote = position::load_remote(&id).await;
+            if let Some(p) = position::latest(local, remote) {
+                go_to(p.segment, p.word);
+            }
+            restored.set(true);
+        });
+        true
+    });
+
+    // save the reading position, on the server only once per segment while
+    // playing
+    let position_id = text_id.clone();
+    Effect::new(move || {
+        let position = Position {
+            segment: segment_index.get(),
+            word: progress.get(),
+            updated: position::now(),
+        };
+        if !restored.get() {
+            return;
+        }
+        position::save_local(&position_id, &position);
+        if !playing.get() || saved_segment.get_value() != Some(position.segment) {
+            saved_segment.set_value(Some(position.segment));
+            let id = position_id.clone();
+            spawn_local(async move { position::save_remote(&id, &position).await });
+        }
+    });
+
+    // jump to an annotation
+    Effect::new(move || {
+        if let Some((index, word)) = jump.get() {
+            jump.set(None);
+            go_to(index, Some(word));
+        }
+    });
+
+    // save annotations
+    let annotations_id = text_id.clone();
+    Effect::new(move || {
+        annotations.with(|a| annotations::save(&annotations_id, a));
+    });
+
+    // save keyboard shortcuts
+    Effect::new(move || bindings.with(|b| b.save()));
+
+    // go to the word before or after the current one, across segments
+    let step_word = move |forward: bool| {
+        let index = segment_index.get_untracked();
+        let word = progress.get_untracked();
+        let words_in = |i: usize| {
+            text.with_untracked(|t| {
+                t.as_ref()
+                    .and_then(|t| t.segments.get(i))
+                    .map(|s| s.words.len() as u32)
+            })
+        };
+        let target = match (forward, word) {
+            (true, None) => Some((index, 0)),
+            (true, Some(w)) if w + 1 < content_length.get_untracked() => Some((index, w + 1)),
+            (true, Some(_)) => words_in(index + 1).map(|_| (index + 1, 0)),
+            (false, Some(w)) if w > 0 => Some((index, w - 1)),
+            (false, _) => index
+                .checked_sub(1)
+                .and_then(|i| Some((i, words_in(i)?.saturating_sub(1)))),
+        };
+        if let Some((index, word)) = target {
+            if playing.get_untracked() {
+                seek.set(Some((index, word)));
             } else {
-                None
+                go_to(index, Some(word));
+            }
+        }
+    };
+
+    // keyboard shortcuts
+    let handle = window_event_listener(ev::keydown, move |e| {
+        if shortcuts::is_typing(&e) {
+            return;
+        }
+        let key = shortcuts::key_name(&e);
+        if let Some(action) = rebinding.get_untracked() {
+            e.prevent_default();
+            rebinding.set(None);
+            if key != "Escape" {
+                bindings.update(|b| b.set(action, key));
+            }
+            return;
+        }
+        if key == "Escape" && help_open.get_untracked() {
+            help_open.set(false);
+            return;
+        }
+        let Some(action) = bindings.with_untracked(|b| b.action(&key)) else {
+            return;
+        };
+        e.prevent_default();
+        match action {
+            Action::PlayPause => playing.update(|p| *p = !*p),
+            Action::PreviousWord => step_word(false),
+            Action::NextWord => step_word(true),
+            Action::PreviousSegment => segment_index.update(|n| *n = n.saturating_sub(1)),
+            Action::NextSegment => segment_index.update(|n| *n = n.saturating_add(1)),
+            Action::BackFive => segment_index.update(|n| *n = n.saturating_sub(5)),
+            Action::ForwardFive => segment_index.update(|n| *n = n.saturating_add(5)),
+            Action::Slower => {
+                rate.update(|r| *r = audio::RATES.iter().copied().rfind(|n| n < r).unwrap_or(*r))
+            }
+            Action::Faster => {
+                rate.update(|r| *r = audio::RATES.iter().copied().find(|n| n > r).unwrap_or(*r))
+            }
+            Action::Repeat => seek.set(Some((segment_index.get_untracked(), 0))),
+            Action::Mute => muted.update(|m| *m = !*m),
+            Action::Help => help_open.update(|o| *o = !*o),
+        }
+    });
+    on_cleanup(move || handle.remove());
+
+    // control playback from the media session of the browser
+    Effect::new(move || {
+        media_session::connect(move |action| match action {
+            media_session::Action::Play => playing.set(true),
+            media_session::Action::Pause | media_session::Action::Stop => playing.set(false),
+            media_session::Action::Previous => segment_index.update(|n| *n = n.saturating_sub(1)),
+            media_session::Action::Next => segment_index.update(|n| *n = n.saturating_add(1)),
+            media_session::Action::SeekTo(time) => {
+                let index = segment_index.get_untracked();
+                let word = segment.with_untracked(|s| s.as_ref().and_then(|s| s.word_at(time)));
+                if let Some(word) = word.map(|w| w as u32) {
+                    if playing.get_untracked() {
+                        seek.set(Some((index, word)));
+                    } else {
+                        go_to(index, Some(word));
+                    }
+                }
             }
-        })(segment.get())
+        })
     });
+    on_cleanup(media_session::disconnect);
 
-    let UseIntervalReturn {
-        counter: update_tick,
-        reset: update_tick_reset,
-        ..
-    } = use_interval(10);
+    // show the current segment of the text in the media session
+    let media_base = base.clone();
+    let media_title = text_id.clone();
+    Effect::new(move || {
+        let index = segment_index.get();
+        text.with(|t| {
+            let Some(t) = t else {
+                return;
+            };
+            let segment = t.segments.get(index).map(|s| {
+                s.words
+                    .iter()
+                    .map(Word::content)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            });
+            media_session::set_metadata(
+                &segment.unwrap_or_default(),
+                t.metadata.title.as_deref().unwrap_or(&media_title),
+                t.metadata.author.as_deref(),
+                t.metadata
+                    .cover
+                    .as_ref()
+                    .map(|c| helper::resolve(&media_base, c))
+                    .as_deref(),
+            );
+        });
+    });
 
-    let UseIntervalReturn {
-        counter: update_scroll_tick,
-        reset: update_scroll_tick_reset,
-        ..
-    } = use_interval(200);
+    // update the playback state and position in the media session, the
+    // browser advances the position while playing
+    Effect::new(move || {
+        let playing = playing.get();
+        let word = progress.get();
+        let rate = rate.get();
+        let Some(segment) = segment.get() else {
+            return;
+        };
+        let position = audio
+            .get_untracked()
+            .and_then(|a| a.progress())
+            .or_else(|| word.and_then(|w| segment.word_start(w as usize)));
+        media_session::set_playing(playing);
+        media_session::set_position(segment.duration, position.unwrap_or(0.0), rate);
+    });
 
-    // load segment from text
+    // apply language of text to document
     Effect::new(move || {
-        if let Some(t) = text_resource.get() {
-            text.set(Some(t.take()));
+        if let Some(t) = text.get() {
+            helper::set_document_language(
+                t.metadata.language.as_deref().unwrap_or(DEFAULT_LANGUAGE),
+            );
         }
     });
+    on_cleanup(|| helper::set_document_language(DEFAULT_LANGUAGE));
+
+    let text_id_view = text_id.clone();
+    let title = move || {
+        text.get()
+            .and_then(|t| t.metadata.title)
+            .unwrap_or_else(|| text_id.clone())
+    };
 
     // load segment from text
     Effect::new(move || {
         if let Some(t) = text.get() {
-            segments_content.set(
-                t.segments
-                    .iter()
-                    .map(|s| s.words.iter().map(|w| w.into()).collect())
-                    .collect(),
-            );
+            segments_content.set(t.segments);
         }
     });
 
@@ -98,44 +384,184 @@ pub(crate) fn Reader() -> impl IntoView {
         content.set(words.get().iter().map(|w| w.into()).collect());
     });
 
-    // load audio from audio resource
-    Effect::new(move || {
-        if let Some(r) = audio_resource.get()
-            && let Some(t) = r.take()
+    // load a shared file once, the segments of it are cut once it's there
+    let load_file = move |url: String, index: usize, playback: Option<Playback>| {
+        if !loading_files
+            .try_update_value(|l| l.insert(url.clone()))
+            .unwrap_or(false)
         {
-            audio.set(Some(t));
+            return;
+        }
+        spawn_local(async move {
+            let progress = move |p| {
+                downloads.update(|d| {
+                    d.insert(index, p);
+                })
+            };
+            let file = if stream(&url, playback).await {
+                Ok(audio::File::stream(&url))
+            } else {
+                match helper::load_bytes(&url, progress).await {
+                    Ok(bytes) => audio::File::new(&bytes).await,
+                    Err(e) => Err(e),
+                }
+            };
+            loading_files.update_value(|l| {
+                l.remove(&url);
+            });
+            downloads.update(|d| {
+                d.remove(&index);
+            });
+            if let Err(e) = &file {
+                logging::error!("{e}");
+            }
+            files.update(|f| {
+                f.insert(url, file);
+            });
+        });
+    };
+
+    // prefetch audio of the current and the neighbouring segments
+    Effect::new(move || {
+        let Some(t) = text.get() else {
+            return;
+        };
+        let index = segment_index.get();
+        let nearby = move |i: usize| i.abs_diff(segment_index.get_untracked()) <= 1;
+        if tracks.with_untracked(|t| t.keys().any(|i| !nearby(*i))) {
+            tracks.update(|t| t.retain(|i, _| nearby(*i)));
+        }
+        for i in index.saturating_sub(1)..=index.saturating_add(1) {
+            let Some(segment) = t.segments.get(i).cloned() else {
+                continue;
+            };
+            // failed segments wait for a retry
+            if tracks.with_untracked(|t| t.contains_key(&i))
+                || track_errors.with(|e| e.contains_key(&i))
+            {
+                continue;
+            }
+            if let common::Audio::Window(window) = &segment.audio {
+                let url = helper::resolve(&base, &window.file);
+                let track = match files.with(|f| f.get(&url).cloned()) {
+                    Some(file) => file.and_then(|f| f.window(window.start, window.end)),
+                    None => {
+                        load_file(url, i, t.metadata.playback);
+                        continue;
+                    }
+                };
+                match track {
+                    Ok(track) => tracks.update(|t| {
+                        t.insert(i, track);
+                    }),
+                    Err(e) => track_errors.update(|t| {
+                        t.insert(i, e);
+                    }),
+                }
+                continue;
+            }
+            if !loading.try_update_value(|l| l.insert(i)).unwrap_or(false) {
+                continue;
+            }
+            let base = base.clone();
+            let playback = t.metadata.playback;
+            spawn_local(async move {
+                let progress = move |p| {
+                    downloads.update(|d| {
+                        d.insert(i, p);
+                    })
+                };
+                let track = load_track(&base, segment, playback, progress).await;
+                loading.update_value(|l| {
+                    l.remove(&i);
+                });
+                downloads.update(|d| {
+                    d.remove(&i);
+                });
+                match track {
+                    Ok(Some(track)) if nearby(i) => tracks.update(|t| {
+                        t.insert(i, track);
+                    }),
+                    Ok(_) => {}
+                    Err(e) => {
+                        logging::error!("{e}");
+                        track_errors.update(|t| {
+                            t.insert(i, e);
+                        });
+                    }
+                }
+            });
         }
     });
 
-    // prevent update tick overflow
+    // take audio of the current segment from the prefetched tracks
     Effect::new(move || {
-        if update_tick.get() >= u16::MAX as u64 {
-            logging::log!("reset update_tick");
-            update_tick_reset();
+        let current = tracks.with(|t| t.get(&segment_index.get()).cloned());
+        if current != audio.get_untracked() {
+            audio.set(current);
         }
     });
 
-    // update audio progress from playing audio
+    // stop audio of the segment before
     Effect::new(move || {
-        update_tick.get();
-        if let Some(audio) = audio.get_untracked() {
-            if let Some(ap) = audio.progress() {
-                audio_progress.set(Some(ap));
-            }
+        let current = audio.get();
+        if let Some(previous) = previous_audio.get_value()
+            && Some(&previous) != current.as_ref()
+        {
+            report(previous.stop());
         }
+        previous_audio.set_value(current);
+    });
+
+    // whether the repetition ending in the segment at `index` plays again
+    let repeats_left = move |index: usize| {
+        let played = repeated.with_value(|(i, n)| if *i == index { *n } else { 0 });
+        repeat_times.get_untracked().is_none_or(|t| played + 1 < t)
+    };
+
+    // reset repetitions when changing how to repeat
+    Effect::new(move || {
+        repeat.get();
+        repeat_times.get();
+        repeated.set_value((usize::MAX, 0));
     });
 
-    // prevent update scroll tick overflow
+    // schedule audio of the next segment to follow the current one
     Effect::new(move || {
-        if update_scroll_tick.get() >= u16::MAX as u64 {
-            logging::log!("reset update_tick");
-            update_scroll_tick_reset();
+        audio_progress.get();
+        let index = segment_index.get();
+        let (current, next) = tracks.with(|t| {
+            (
+                t.get(&index).cloned(),
+                t.get(&index.saturating_add(1)).cloned(),
+            )
+        });
+        let Some(next) = next else {
+            return;
+        };
+        let (duration, repeats) = text.with(|t| {
+            let segment = t.as_ref().and_then(|t| t.segments.get(index));
+            (
+                segment.map(|s| s.duration),
+                segment.and_then(|s| repeat.get().end(index, s)).is_some() && repeats_left(index),
+            )
+        });
+        if !playing.get() || repeats {
+            report(next.stop());
+        } else if let Some(current) = current
+            && let Some(duration) = duration
+        {
+            report(next.play_after(&current, duration + pause.get() * rate.get()));
         }
     });
 
-    // scroll to current word
+    // update audio progress from playing audio
+    audio::publish_progress(audio.into(), playing.into(), audio_progress, playback_error);
+
+    // scroll to current word, or to the current segment while paused
     Effect::new(move || {
-        update_scroll_tick.get();
+        progress.get();
+        segment_index.get();
         let element = {
             if let Some(e) = document().get_elements_by_class_name("word active").item(0) {
                 Some(e)
@@ -158,19 +584,14 @@ pub(crate) fn Reader() -> impl IntoView {
         }
     });
 
-    // update progress on audio progress change
+    // word timings of the segment
+    let timeline =
+        Memo::new(move |_| segment.with(|s| s.as_ref().map(Timeline::new).unwrap_or_default()));
+
+    // update progress when the audio reaches another word
     Effect::new(move || {
         if let Some(ap) = audio_progress.get() {
-            let new_progress = words.get().iter().enumerate().find_map(|e| {
-                if let Word::Timestamped { start, end, .. } = e.1
-                    && start <= &ap
-                    && end >= &ap
-                {
-                    Some(e.0 as u32)
-                } else {
-                    None
-                }
-            });
+            let new_progress = timeline.with(|t| t.word_at(ap)).map(|w| w as u32);
             if new_progress != progress.get_untracked() && new_progress.is_some() {
                 progress_from_audio.set(new_progress);
                 progress.set(new_progress);
@@ -190,6 +611,7 @@ pub(crate) fn Reader() -> impl IntoView {
         segment_index.get();
         if audio_progress.get_untracked().is_some() {
             playing.set(false);
+            audio_progress.set(None);
         }
     });
 
@@ -203,14 +625,59 @@ pub(crate) fn Reader() -> impl IntoView {
         }
     });
 
-    // automatically go to the next page
+    // repeat the looped part after a gap, the audio is paused during the gap
+    Effect::new(move || {
+        let Some(ap) = audio_progress.get() else {
+            return;
+        };
+        let index = segment_index.get_untracked();
+        let mode = repeat.get_untracked();
+        let Some(end) = segment.with_untracked(|s| s.as_ref().and_then(|s| mode.end(index, s)))
+        else {
+            return;
+        };
+        if ap < end || repeat_waiting.get_value() || !repeats_left(index) {
+            return;
+        }
+        repeat_waiting.set_value(true);
+        repeated.update_value(|(i, n)| {
+            if *i == index {
+                *n += 1
+            } else {
+                *i = index;
+                *n = 1
+            }
+        });
+        if let Some(a) = audio.get_untracked() {
+            report(a.pause());
+        }
+        set_timeout(
+            move || {
+                repeat_waiting.set_value(false);
+                if playing.get_untracked() {
+                    seek.set(Some(mode.start(index)));
+                }
+            },
+            Duration::from_secs_f64(repeat_gap.get_untracked()),
+        );
+    });
+
+    // automatically go to the next page, audio progress is in seconds of the
+    // audio so the pause is scaled to last as long at every rate
     Effect::new(move || {
         if let Some(ap) = audio_progress.get()
             && let Some(segment) = segment.get()
-            && ap >= segment.duration + 0.3
+            && ap >= segment.duration + pause.get_untracked() * rate.get_untracked()
+            && !repeat_waiting.get_value()
+            && (repeat
+                .get_untracked()
+                .end(segment_index.get_untracked(), &segment)
+                .is_none()
+                || !repeats_left(segment_index.get_untracked()))
         {
-            audio.set(None);
+            // the next audio is already playing, don't seek it to the last word
             audio_progress.set(None);
+            progress.set(None);
             segment_index.update(|n| {
                 if *n < usize::MAX {
                     *n += 1
@@ -225,47 +692,128 @@ pub(crate) fn Reader() -> impl IntoView {
         content_length.set(content_clone.get().len().try_into().unwrap_or(0));
     });
 
-    // reset progress on content change
+    // reset progress on content change, unless restoring it
     let content_clone = content.clone();
     Effect::new(move || {
         content_clone.get();
-        progress.update(move |o| *o = None);
+        let restored = restore
+            .try_update_value(|r| r.take_if(|(s, _)| *s == segment_index.get_untracked()))
+            .flatten();
+        progress.set(restored.map(|(_, w)| w));
+    });
+
+    // play from a clicked word, switching to its segment first
+    Effect::new(move || {
+        let Some((index, word)) = seek.get() else {
+            return;
+        };
+        if index != segment_index.get_untracked() {
+            audio.set(None);
+            audio_progress.set(None);
+            segment_index.set(index);
+            return;
+        }
+        // wait for the audio of the segment
+        let Some(a) = audio.get() else {
+            return;
+        };
+        seek.set(None);
+        report(a.pause());
+        progress_from_audio.set(Some(word));
+        progress.set(Some(word));
+        playing.set(true);
+    });
+
+    // apply playback rate to audio
+    Effect::new(move || {
+        if let Some(a) = audio.get() {
+            report(a.set_rate(rate.get()));
+        }
+    });
+
+    // apply and remember the volume
+    Effect::new(move || {
+        let (volume, muted) = (volume.get(), muted.get());
+        report(audio::set_volume(if muted { 0.0 } else { volume }));
+        helper::save_local(VOLUME_KEY, &(volume, muted));
     });
 
     // play or pause audio depending on playing state
     Effect::new(move || match (playing.get(), audio.get()) {
         (true, Some(a)) => {
             if let Some(p) = progress.get_untracked()
-                && let Some(w) = words.get_untracked().get(p as usize)
-                && let Word::Timestamped { start, .. } = w
+                && let Some(start) = segment
+                    .get_untracked()
+                    .and_then(|s| s.word_start(p as usize))
             {
-                a.play_at(*start);
+                report(a.play_at(start));
             } else {
-                a.play();
+                report(a.play());
             }
         }
-        (false, Some(a)) => a.pause(),
+        (false, Some(a)) => report(a.pause()),
         _ => {}
     });
 
     mview! {
+        Title text={title};
         div.reader {
-            controls::Controls page={segment_index} {playing} {progress} content_length={content_length.read_only()};
+            controls::Controls page={segment_index} {playing} {progress} {rate} {pause}
+                {repeat} {repeat_times} {repeat_gap} {volume} {muted}
+                content_length={content_length.read_only()} {annotations_open} {help_open};
+            annotations::Annotations
+                text_id={text_id_view}
+                {text}
+                {annotations}
+                page={segment_index}
+                {jump}
+                open={annotations_open};
+            shortcuts::Help {bindings} {rebinding} open={help_open};
+            ErrorMessage {error} {retry};
+            controls::Loading progress={
+                Signal::derive(move || downloads.with(|d| d.get(&segment_index.get()).copied()))
+            };
             div.content-container {
                 div.content {
-                    {
-                        move || segments_content.get()
-                        .iter()
-                        .enumerate()
-                        .map(|(i, c)| {
-                            let is_active = segment_index.get() == i;
-                            mview! {
-                                segment::Segment text={c.clone()} active={is_active} highlight={progress.read_only()};
-                            }
-                        }).collect_view()
-                    }
+                    block::Blocks segments={segments_content} active={segment_index} highlight={progress.read_only()}
+                        {seek} {annotations};
                 }
             }
         }
     }
 }
+
+/// Whether to stream the file at `url` instead of decoding it, as the text
+/// says or if it is large.
+async fn stream(url: &str, playback: Option<Playback>) -> bool {
+    match playback {
+        Some(Playback::Stream) => true,
+        Some(Playback::Decode) => false,
+        None => helper::content_length(url)
+            .await
+            .is_some_and(|length| length > STREAM_SIZE),
+    }
+}
+
+async fn load_track(
+    base: &str,
+    segment: Segment,
+    playback: Option<Playback>,
+    progress: impl Fn(helper::Progress),
+) -> Result<Option<Track>, Error> {
+    let bytes = match segment.audio {
+        // windows of shared files are cut when prefetching
+        common::Audio::None | common::Audio::Window(_) => return Ok(None),
+        common::Audio::Wav(wav) => wav
+            .decode()
+            .map_err(|e| Error::decode("embedded audio", e))?,
+        common::Audio::Ref(url) => {
+            let url = helper::resolve(base, &url);
+            if stream(&url, playback).await {
+                return Ok(Some(Track::stream(&url, 0.0, None)?));
+            }
+            helper::load_bytes(&url, progress).await?
+        }
+    };
+    Ok(Some(Track::new(&bytes).await?))
+}
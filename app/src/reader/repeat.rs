@@ -0,0 +1,96 @@
+use common::{Segment, annotation::Location};
+use leptos::{ev, prelude::*};
+use leptos_mview::mview;
+
+use super::annotations;
+
+/// Number of times the looped part is played, `None` loops it until the mode
+/// changes.
+const TIMES: [Option<u32>; 5] = [None, Some(2), Some(3), Some(5), Some(10)];
+/// Gaps between repetitions in seconds selectable in the reader.
+const GAPS: [f64; 5] = [0.0, 0.5, 1.0, 2.0, 5.0];
+pub(crate) const DEFAULT_GAP: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Mode {
+    /// Segments play one after another.
+    #[default]
+    Off,
+    /// Each segment is repeated before going on to the next.
+    Segment,
+    /// The words from the first to the last location, inclusive, are repeated.
+    Range(Location, Location),
+}
+
+impl Mode {
+    /// Seconds into `segment`, the one at `index`, at which a repetition
+    /// ends, if one ends in it.
+    pub(crate) fn end(self, index: usize, segment: &Segment) -> Option<f64> {
+        match self {
+            Mode::Off => None,
+            Mode::Segment => Some(segment.duration),
+            Mode::Range(_, end) if end.segment == index => segment.word_end(end.word),
+            Mode::Range(..) => None,
+        }
+    }
+
+    /// Segment and word a repetition ending in the segment at `index` starts
+    /// from.
+    pub(crate) fn start(self, index: usize) -> (usize, u32) {
+        match self {
+            Mode::Range(start, _) => (start.segment, start.word as u32),
+            _ => (index, 0),
+        }
+    }
+}
+
+#[component]
+pub(crate) fn RepeatButtons(
+    #[prop(into)] mode: RwSignal<Mode>,
+    #[prop(into)] times: RwSignal<Option<u32>>,
+    #[prop(into)] gap: RwSignal<f64>,
+) -> impl IntoView {
+    // cycle through off, the segment and the selected words if there are any
+    let next_mode = move |_| {
+        mode.update(|m| {
+            *m = match m {
+                Mode::Off => Mode::Segment,
+                Mode::Segment => annotations::selected_words()
+                    .map_or(Mode::Off, |(start, end)| Mode::Range(start, end)),
+                Mode::Range(..) => Mode::Off,
+            }
+        })
+    };
+    let next_times = move |_| {
+        times.update(|t| {
+            let i = TIMES.iter().position(|n| n == t).unwrap_or(0);
+            *t = TIMES[(i + 1) % TIMES.len()];
+        })
+    };
+    let next_gap =
+        move |_| gap.update(|g| *g = GAPS.iter().copied().find(|n| n > g).unwrap_or(GAPS[0]));
+    // keep the selection in the text when pressing the button
+    let keep_selection = |e: ev::MouseEvent| e.prevent_default();
+    let off = move || mode.get() == Mode::Off;
+
+    mview! {
+        button.repeat
+            title="loop the segment or the selected words"
+            on:mousedown={keep_selection}
+            on:click={next_mode} {
+            {
+                move || match mode.get() {
+                    Mode::Off => "↻ off",
+                    Mode::Segment => "↻ segment",
+                    Mode::Range(..) => "↻ words",
+                }
+            }
+        }
+        button.times class:off={off} title="times to play" on:click={next_times} {
+            { move || times.get().map_or("×∞".to_string(), |t| format!("×{t}")) }
+        }
+        button.gap class:off={off} title="gap between repetitions" on:click={next_gap} {
+            { move || format!("↻+{}s", gap.get()) }
+        }
+    }
+}
@@ -1,16 +1,27 @@
+use common::{
+    MarkKind,
+    annotation::{Annotation, Kind, Location},
+};
 use leptos::prelude::*;
 use leptos_mview::mview;
 
+use super::annotations;
+
 #[component]
 pub(crate) fn Segment(
-    #[prop(into)] text: Signal<Vec<String>>,
+    #[prop(into)] segment: Signal<common::Segment>,
+    index: usize,
+    #[prop(into)] seek: RwSignal<Option<(usize, u32)>>,
     #[prop(into)] highlight: Signal<Option<u32>>,
     #[prop(into)] active: Signal<bool>,
+    #[prop(into)] annotations: Signal<Vec<Annotation>>,
 ) -> impl IntoView {
     mview! {
         span.segment class:active={active} {
         {
-            move || text.get()
+            move || {
+                let segment = segment.get();
+                segment.words
                 .iter()
                 .enumerate()
                 .map(|(i, w)| {
@@ -19,25 +30,78 @@ pub(crate) fn Segment(
                     } else {
                         false
                     };
+                    let location = Location { segment: index, word: i };
+                    let (color, bookmarked) = annotations.with(|a| {
+                        let covering = || a.iter().filter(|a| a.contains(location));
+                        (
+                            covering().find_map(|a| match a.kind {
+                                Kind::Highlight(c) => Some(c.name()),
+                                Kind::Bookmark => None,
+                            }),
+                            covering().any(|a| a.kind == Kind::Bookmark),
+                        )
+                    });
                     mview! {
                         Word
-                            text={w.to_string()}
-                            active={is_active};
+                            text={String::from(w)}
+                            {location}
+                            active={is_active}
+                            {color}
+                            {bookmarked}
+                            marks={segment.marks_at(i)}
+                            line_break={segment.line_break_after(i)}
+                            on_click={move || {
+                                // selecting words to annotate them shouldn't seek
+                                if annotations::selected_words().is_none() {
+                                    seek.set(Some((index, i as u32)))
+                                }
+                            }};
                     }
                 }).collect_view()
+            }
         }
         }
     }
 }
 
 #[component]
-fn Word(#[prop(into)] text: String, active: bool) -> impl IntoView {
-    mview! {
-        span.word class:active={move || active} {
+fn Word(
+    #[prop(into)] text: String,
+    location: Location,
+    active: bool,
+    /// Color of a highlight on the word.
+    color: Option<&'static str>,
+    bookmarked: bool,
+    marks: Vec<MarkKind>,
+    line_break: bool,
+    on_click: impl Fn() + 'static,
+) -> impl IntoView {
+    let mut word = mview! {
+        span.word
+            class:active={move || active}
+            class:bookmarked={bookmarked}
+            data-highlight={color}
+            data-segment={location.segment}
+            data-word={location.word}
+            on:click={move |_| on_click()} {
             { text }
         }
-        span {
-            " "
-        }
+    }
+    .into_any();
+    for mark in marks.into_iter().rev() {
+        word = match mark {
+            MarkKind::Emphasis => mview! { em { {word} } }.into_any(),
+            MarkKind::Strong => mview! { strong { {word} } }.into_any(),
+            MarkKind::Code => mview! { code { {word} } }.into_any(),
+        };
+    }
+    let separator = if line_break {
+        mview! { br; }.into_any()
+    } else {
+        mview! { span { " " } }.into_any()
+    };
+    mview! {
+        {word}
+        {separator}
     }
 }
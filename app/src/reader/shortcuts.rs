@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use leptos::{ev::KeyboardEvent, prelude::*};
+use leptos_mview::mview;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+use crate::helper;
+
+const STORAGE_KEY: &str = "literacy.shortcuts";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Action {
+    PlayPause,
+    PreviousWord,
+    NextWord,
+    PreviousSegment,
+    NextSegment,
+    BackFive,
+    ForwardFive,
+    Slower,
+    Faster,
+    Repeat,
+    Mute,
+    Help,
+}
+
+impl Action {
+    const ALL: [Action; 12] = [
+        Action::PlayPause,
+        Action::PreviousWord,
+        Action::NextWord,
+        Action::PreviousSegment,
+        Action::NextSegment,
+        Action::BackFive,
+        Action::ForwardFive,
+        Action::Slower,
+        Action::Faster,
+        Action::Repeat,
+        Action::Mute,
+        Action::Help,
+    ];
+
+    fn description(self) -> &'static str {
+        match self {
+            Action::PlayPause => "play or pause",
+            Action::PreviousWord => "previous word",
+            Action::NextWord => "next word",
+            Action::PreviousSegment => "previous segment",
+            Action::NextSegment => "next segment",
+            Action::BackFive => "5 segments back",
+            Action::ForwardFive => "5 segments forward",
+            Action::Slower => "slower",
+            Action::Faster => "faster",
+            Action::Repeat => "repeat segment",
+            Action::Mute => "mute",
+            Action::Help => "show shortcuts",
+        }
+    }
+
+    fn default_key(self) -> &'static str {
+        match self {
+            Action::PlayPause => "Space",
+            Action::PreviousWord => "ArrowLeft",
+            Action::NextWord => "ArrowRight",
+            Action::PreviousSegment => "ArrowUp",
+            Action::NextSegment => "ArrowDown",
+            Action::BackFive => "Shift+ArrowLeft",
+            Action::ForwardFive => "Shift+ArrowRight",
+            Action::Slower => "[",
+            Action::Faster => "]",
+            Action::Repeat => "r",
+            Action::Mute => "m",
+            Action::Help => "?",
+        }
+    }
+}
+
+/// Keys bound to actions, saved in the browser.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct Bindings(BTreeMap<Action, String>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self(
+            Action::ALL
+                .into_iter()
+                .map(|a| (a, a.default_key().to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl Bindings {
+    /// Saved bindings, actions without one keep their default.
+    pub(crate) fn load() -> Self {
+        let mut bindings = Self::default();
+        if let Some(Bindings(saved)) = helper::load_local(STORAGE_KEY) {
+            bindings.0.extend(saved);
+        }
+        bindings
+    }
+
+    pub(crate) fn save(&self) {
+        helper::save_local(STORAGE_KEY, self)
+    }
+
+    pub(crate) fn action(&self, key: &str) -> Option<Action> {
+        self.0.iter().find(|(_, k)| *k == key).map(|(a, _)| *a)
+    }
+
+    /// Binds `key` to `action`, unbinding it from any other action.
+    pub(crate) fn set(&mut self, action: Action, key: String) {
+        self.0.retain(|_, k| *k != key);
+        self.0.insert(action, key);
+    }
+}
+
+/// Name of the pressed key with modifiers, like `Shift+ArrowLeft`.
+///
+/// Shift is left out for printable keys, it already changes the key itself.
+pub(crate) fn key_name(e: &KeyboardEvent) -> String {
+    let key = match e.key().as_str() {
+        " " => "Space".to_string(),
+        key => key.to_string(),
+    };
+    let printable = key.chars().count() == 1;
+    let mut name = String::new();
+    for (pressed, modifier) in [
+        (e.ctrl_key(), "Ctrl+"),
+        (e.alt_key(), "Alt+"),
+        (e.meta_key(), "Meta+"),
+        (e.shift_key() && !printable, "Shift+"),
+    ] {
+        if pressed {
+            name.push_str(modifier);
+        }
+    }
+    name + &key
+}
+
+/// Whether the key goes to a text field instead of the reader.
+pub(crate) fn is_typing(e: &KeyboardEvent) -> bool {
+    e.target()
+        .and_then(|t| t.dyn_into::<Element>().ok())
+        .is_some_and(|t| matches!(t.tag_name().as_str(), "INPUT" | "TEXTAREA"))
+}
+
+/// Overlay listing the shortcuts, a binding is changed by clicking it and
+/// pressing the new key.
+#[component]
+pub(crate) fn Help(
+    #[prop(into)] bindings: RwSignal<Bindings>,
+    /// Action waiting for the next key pressed to bind it.
+    #[prop(into)]
+    rebinding: RwSignal<Option<Action>>,
+    #[prop(into)] open: RwSignal<bool>,
+) -> impl IntoView {
+    let rows = move || {
+        Action::ALL
+            .into_iter()
+            .map(|action| {
+                let key = move || {
+                    if rebinding.get() == Some(action) {
+                        "press a key".to_string()
+                    } else {
+                        bindings.with(|b| b.0.get(&action).cloned().unwrap_or_default())
+                    }
+                };
+                mview! {
+                    tr {
+                        td { {action.description()} }
+                        td {
+                            button.key on:click={move |_| rebinding.set(Some(action))} {
+                                kbd { {key} }
+                            }
+                        }
+                    }
+                }
+            })
+            .collect_view()
+    };
+
+    mview! {
+        div.help class:open={open} on:click={move |_| open.set(false)} {
+            div.shortcuts on:click={|e| e.stop_propagation()} {
+                table { {rows} }
+                button.reset on:click={move |_| bindings.set(Bindings::default())} {
+                    "reset"
+                }
+            }
+        }
+    }
+}
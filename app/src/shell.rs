@@ -2,10 +2,13 @@ use leptos::{config::LeptosOptions, prelude::*};
 use leptos_meta::MetaTags;
 use leptos_mview::mview;
 
+/// Language of the document when no text with a language is open.
+pub(crate) const DEFAULT_LANGUAGE: &str = "en";
+
 pub fn shell(options: LeptosOptions) -> impl IntoView {
     mview! {
         {view!{<!DOCTYPE html>}}
-        html lang="en" {
+        html lang={DEFAULT_LANGUAGE} {
             head {
                 meta charset="utf-8";
                 meta name="viewport" content="width=device-width, initial-scale=1";
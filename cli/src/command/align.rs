@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use common::{Audio, FORMAT_VERSION, Metadata, Segment, Text, align::Alignment};
+
+use crate::{
+    Error,
+    config::{AlignmentFormat, Config},
+    document,
+    step::Vars,
+};
+
+pub(crate) const DEFAULT_MIN_CONFIDENCE: f64 = 0.6;
+
+/// Align the words of a plain text file with the result of an aligner.
+///
+/// Writes a document with one segment of timestamped words and reports the
+/// words that were aligned with low confidence.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Plain text file with the original words.
+    text: PathBuf,
+    /// Json result of the aligner.
+    alignment: PathBuf,
+    #[arg(short, long, value_enum, default_value_t)]
+    format: AlignmentFormat,
+    /// Audio file the alignment was made from, referenced by the segment.
+    #[arg(long)]
+    audio: Option<String>,
+    /// Duration of the audio in seconds, probed with the duration step if
+    /// unset.
+    #[arg(long)]
+    duration: Option<f64>,
+    /// Config with the duration step, defaults to `./literacy.toml`.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    #[arg(short, long)]
+    out: PathBuf,
+    /// Report aligned words below this confidence.
+    #[arg(long, default_value_t = DEFAULT_MIN_CONFIDENCE)]
+    min_confidence: f64,
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Error> {
+    let original = std::fs::read_to_string(&args.text).map_err(Error::io(&args.text))?;
+    let original: Vec<String> = original.split_whitespace().map(String::from).collect();
+    let json = std::fs::read_to_string(&args.alignment).map_err(Error::io(&args.alignment))?;
+    let recognized = args.format.parse(&json)?;
+
+    let out_dir = document::dir(&args.out);
+    let audio_file = args
+        .audio
+        .as_ref()
+        .and_then(|a| document::resolve(&out_dir, a));
+    let duration = match (args.duration, &audio_file) {
+        (Some(d), _) => d,
+        (None, Some(file)) => {
+            let config = Config::load(args.config.as_deref())?;
+            let output = config
+                .duration
+                .run("duration", &Vars::default().with("input", file.display()))?;
+            output.trim().parse().map_err(|_| Error::Step {
+                step: "duration",
+                message: format!("expected seconds, got {:?}", output.trim()),
+            })?
+        }
+        (None, None) => recognized.iter().filter_map(|w| w.end).fold(0.0, f64::max),
+    };
+
+    let alignment = common::align::align(&original, &recognized, duration);
+    print_report(&alignment, args.min_confidence);
+
+    let text = Text {
+        format_version: FORMAT_VERSION,
+        metadata: Metadata::default(),
+        segments: vec![Segment {
+            words: alignment.words,
+            audio: args.audio.map(Audio::Ref).unwrap_or(Audio::None),
+            duration,
+            ..Default::default()
+        }],
+    };
+    document::write(&args.out, &text)
+}
+
+/// Prints the words aligned below `min_confidence` and the falsely detected
+/// words to stderr.
+pub(crate) fn print_report(alignment: &Alignment, min_confidence: f64) {
+    for (i, r) in alignment.report.iter().enumerate() {
+        if r.confidence < min_confidence {
+            eprintln!(
+                "word {i} {:?}: {} with {:?}, confidence {:.2}",
+                r.content, r.kind, r.recognized, r.confidence
+            );
+        }
+    }
+    for w in &alignment.falsely_detected {
+        eprintln!("falsely detected {:?}", w.content);
+    }
+}
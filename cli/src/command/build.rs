@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use common::{Audio, FORMAT_VERSION, Generator, Metadata, Segment, Text, Wav, Word};
+
+use crate::{
+    Error,
+    command::align,
+    config::Config,
+    document,
+    markdown::{self, Sentence},
+    step::{Step, Vars},
+};
+
+/// Build a text document from a plain text or Markdown file.
+///
+/// The text is split into sentences, every sentence becomes a segment with
+/// audio from the tts step and, with an aligner, word timestamps. Markdown
+/// (`.md`) keeps its headings, lists, quotes, emphasis and line breaks.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Plain text or Markdown file to build the document from.
+    input: PathBuf,
+    /// Folder to write the document and its audio files to.
+    #[arg(short, long)]
+    out: PathBuf,
+    /// File name of the document in the output folder.
+    #[arg(long, default_value = "text.json")]
+    name: String,
+    /// Config with the external steps, defaults to `./literacy.toml`.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Folder for intermediate files, defaults to `<input>_processing`.
+    #[arg(long)]
+    work_dir: Option<PathBuf>,
+    /// Embed the audio in the document instead of writing audio files.
+    #[arg(long)]
+    embed: Option<Embedding>,
+    /// Report aligned words below this confidence.
+    #[arg(long, default_value_t = align::DEFAULT_MIN_CONFIDENCE)]
+    min_confidence: f64,
+    #[command(flatten)]
+    metadata: MetadataArgs,
+}
+
+#[derive(clap::Args)]
+struct MetadataArgs {
+    #[arg(long)]
+    title: Option<String>,
+    #[arg(long)]
+    author: Option<String>,
+    /// BCP 47 language tag, also passed to the steps as `{language}`.
+    #[arg(long)]
+    language: Option<String>,
+    #[arg(long)]
+    level: Option<String>,
+    #[arg(long)]
+    source: Option<String>,
+    #[arg(long)]
+    license: Option<String>,
+    #[arg(long)]
+    cover: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Embedding {
+    Raw,
+    Compressed,
+    Base64,
+    Base64Compressed,
+}
+
+impl Embedding {
+    fn wav(self, bytes: Vec<u8>) -> Wav {
+        match self {
+            Embedding::Raw => Wav::raw(bytes),
+            Embedding::Compressed => Wav::compressed(&bytes),
+            Embedding::Base64 => Wav::base64(&bytes),
+            Embedding::Base64Compressed => Wav::base64_compressed(&bytes),
+        }
+    }
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Error> {
+    let config = Config::load(args.config.as_deref())?;
+    let tts = config
+        .tts
+        .as_ref()
+        .ok_or_else(|| Error::Usage("no tts step configured".to_string()))?;
+
+    let input = std::fs::read_to_string(&args.input).map_err(Error::io(&args.input))?;
+    let sentences = match args.input.extension().and_then(|e| e.to_str()) {
+        Some("md" | "markdown") => markdown::sentences(&input),
+        _ => split_sentences(&input)
+            .iter()
+            .map(|s| Sentence {
+                words: s.split_whitespace().map(String::from).collect(),
+                ..Default::default()
+            })
+            .collect(),
+    };
+
+    let work_dir = args.work_dir.clone().unwrap_or_else(|| {
+        let stem = args.input.file_stem().unwrap_or_default().to_string_lossy();
+        PathBuf::from(format!("{stem}_processing"))
+    });
+    std::fs::create_dir_all(&work_dir).map_err(Error::io(&work_dir))?;
+    std::fs::create_dir_all(&args.out).map_err(Error::io(&args.out))?;
+
+    let language = args.metadata.language.clone().unwrap_or_default();
+    let total = sentences.len();
+    let mut segments = vec![];
+    for (i, sentence) in sentences.into_iter().enumerate() {
+        let content = sentence.text();
+        eprintln!("[{}/{}] {content}", i + 1, total);
+
+        let text_file = work_dir.join(format!("{i}.txt"));
+        std::fs::write(&text_file, &content).map_err(Error::io(&text_file))?;
+        let wav_file = work_dir.join(format!("{i}.wav"));
+        let vars = Vars::default()
+            .with("index", i)
+            .with("language", &language)
+            .with("text", &content)
+            .with("text_file", text_file.display())
+            .with("work", work_dir.display());
+
+        tts.run("tts", &vars.clone().with("output", wav_file.display()))?;
+
+        let audio_dir = if args.embed.is_some() {
+            &work_dir
+        } else {
+            &args.out
+        };
+        let audio_file = match &config.encoder {
+            Some(encoder) => {
+                let file = audio_dir.join(format!("{i}.{}", encoder.extension));
+                encoder.step.run(
+                    "encoder",
+                    &vars
+                        .clone()
+                        .with("input", wav_file.display())
+                        .with("output", file.display()),
+                )?;
+                file
+            }
+            None => {
+                let file = audio_dir.join(format!("{i}.wav"));
+                if file != wav_file {
+                    std::fs::copy(&wav_file, &file).map_err(Error::io(&file))?;
+                }
+                file
+            }
+        };
+
+        let duration = duration(
+            &config.duration,
+            &vars.clone().with("input", audio_file.display()),
+        )?;
+
+        let words = match &config.aligner {
+            Some(aligner) => {
+                let vars = vars.clone().with("audio", wav_file.display());
+                let output = aligner.step.run("aligner", &vars)?;
+                let output = match &aligner.output {
+                    Some(file) => {
+                        let file = vars.expand(file);
+                        std::fs::read_to_string(&file).map_err(Error::io(file))?
+                    }
+                    None => output,
+                };
+                let recognized = aligner.format.parse(&output)?;
+                let alignment = common::align::align(&sentence.words, &recognized, duration);
+                align::print_report(&alignment, args.min_confidence);
+                alignment.words
+            }
+            None => sentence.words.into_iter().map(Word::Raw).collect(),
+        };
+
+        let audio = match args.embed {
+            Some(embedding) => {
+                let bytes = std::fs::read(&audio_file).map_err(Error::io(&audio_file))?;
+                Audio::Wav(embedding.wav(bytes))
+            }
+            None => Audio::Ref(file_name(&audio_file)),
+        };
+
+        segments.push(Segment {
+            words,
+            audio,
+            duration,
+            block: sentence.block,
+            marks: sentence.marks,
+            line_breaks: sentence.line_breaks,
+        });
+    }
+
+    let text = Text {
+        format_version: FORMAT_VERSION,
+        metadata: metadata(args.metadata),
+        segments,
+    };
+    document::write(&args.out.join(&args.name), &text)
+}
+
+/// Splits a text into sentences at line breaks, `.`, `!`, `?` and `:`.
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = vec![];
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            if !current.is_empty() && !current.ends_with(' ') {
+                current.push(' ');
+            }
+            if c == '\n' {
+                sentences.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        current.push(c);
+        let ends_sentence = match c {
+            '!' | '?' => true,
+            '.' | ':' => chars.peek().is_none_or(|n| n.is_whitespace()),
+            _ => false,
+        };
+        if ends_sentence {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    sentences.push(current);
+    sentences
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn duration(step: &Step, vars: &Vars) -> Result<f64, Error> {
+    let output = step.run("duration", vars)?;
+    output.trim().parse().map_err(|_| Error::Step {
+        step: "duration",
+        message: format!("expected seconds, got {:?}", output.trim()),
+    })
+}
+
+fn metadata(args: MetadataArgs) -> Metadata {
+    Metadata {
+        title: args.title,
+        author: args.author,
+        language: args.language,
+        level: args.level,
+        source: args.source,
+        license: args.license,
+        cover: args.cover,
+        generator: Some(Generator {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+        playback: None,
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+
+use crate::{Error, document, epub};
+
+/// Export a text document to another format.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    document: PathBuf,
+    #[arg(short, long)]
+    out: PathBuf,
+    /// Format of the output, guessed from the extension of the output if
+    /// unset.
+    #[arg(short, long)]
+    format: Option<ExportFormat>,
+    /// Add inline timestamps for every timestamped word to WebVTT cues.
+    #[arg(long)]
+    word_timing: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub(crate) enum ExportFormat {
+    Vtt,
+    Srt,
+    /// EPUB 3 with media overlays for read-along, includes the audio.
+    Epub,
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Error> {
+    let text = document::read(&args.document)?;
+    let format = match args.format {
+        Some(f) => f,
+        None => match args.out.extension().and_then(|e| e.to_str()) {
+            Some("vtt") => ExportFormat::Vtt,
+            Some("srt") => ExportFormat::Srt,
+            Some("epub") => ExportFormat::Epub,
+            _ => {
+                return Err(Error::Usage(
+                    "unknown output format, set --format".to_string(),
+                ));
+            }
+        },
+    };
+    let output = match format {
+        ExportFormat::Vtt => common::subtitle::to_vtt(&text, args.word_timing),
+        ExportFormat::Srt => common::subtitle::to_srt(&text),
+        ExportFormat::Epub => {
+            let file = std::fs::File::create(&args.out).map_err(Error::io(&args.out))?;
+            return epub::write(&text, &document::dir(&args.document), file);
+        }
+    };
+    std::fs::write(&args.out, output).map_err(Error::io(&args.out))
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+
+use crate::{Error, document};
+
+/// Import a text document from subtitles.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    subtitles: PathBuf,
+    #[arg(short, long)]
+    out: PathBuf,
+    /// Format of the input, guessed from its extension if unset.
+    #[arg(short, long)]
+    format: Option<ImportFormat>,
+    /// Document to keep metadata, audio and segment durations from, it must
+    /// have as many segments as the subtitles have cues.
+    #[arg(long)]
+    onto: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub(crate) enum ImportFormat {
+    Vtt,
+    Srt,
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Error> {
+    let input = std::fs::read_to_string(&args.subtitles).map_err(Error::io(&args.subtitles))?;
+    let format = match args.format {
+        Some(f) => f,
+        None => match args.subtitles.extension().and_then(|e| e.to_str()) {
+            Some("vtt") => ImportFormat::Vtt,
+            Some("srt") => ImportFormat::Srt,
+            _ => {
+                return Err(Error::Usage(
+                    "unknown input format, set --format".to_string(),
+                ));
+            }
+        },
+    };
+    let parsed = match format {
+        ImportFormat::Vtt => common::subtitle::from_vtt(&input),
+        ImportFormat::Srt => common::subtitle::from_srt(&input),
+    };
+    let mut text =
+        parsed.map_err(|e| Error::Usage(format!("{}: {e}", args.subtitles.display())))?;
+
+    if let Some(onto) = &args.onto {
+        let mut original = document::read(onto)?;
+        document::rebase(
+            &mut original,
+            &document::dir(onto),
+            &document::dir(&args.out),
+        )?;
+        if original.segments.len() != text.segments.len() {
+            return Err(Error::Usage(format!(
+                "{} has {} segments but the subtitles have {} cues",
+                onto.display(),
+                original.segments.len(),
+                text.segments.len()
+            )));
+        }
+        for (segment, original) in text.segments.iter_mut().zip(original.segments) {
+            segment.audio = original.audio;
+            segment.duration = original.duration;
+        }
+        text.metadata = original.metadata;
+    }
+
+    document::write(&args.out, &text)
+}
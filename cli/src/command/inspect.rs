@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use common::{Audio, Playback, Word};
+
+use crate::{Error, document};
+
+/// Print a summary of a text document.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    document: PathBuf,
+    /// Also list every segment.
+    #[arg(long)]
+    segments: bool,
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Error> {
+    let text = document::read(&args.document)?;
+
+    let metadata = &text.metadata;
+    let fields = [
+        ("title", &metadata.title),
+        ("author", &metadata.author),
+        ("language", &metadata.language),
+        ("level", &metadata.level),
+        ("source", &metadata.source),
+        ("license", &metadata.license),
+        ("cover", &metadata.cover),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("{name}: {value}");
+        }
+    }
+    if let Some(g) = &metadata.generator {
+        println!(
+            "generator: {} {}",
+            g.name,
+            g.version.as_deref().unwrap_or_default()
+        );
+    }
+    if let Some(playback) = metadata.playback {
+        let playback = match playback {
+            Playback::Decode => "decode",
+            Playback::Stream => "stream",
+        };
+        println!("playback: {playback}");
+    }
+    println!("format version: {}", text.format_version);
+
+    let words = text.segments.iter().map(|s| s.words.len()).sum::<usize>();
+    let timestamped = text
+        .segments
+        .iter()
+        .flat_map(|s| &s.words)
+        .filter(|w| matches!(w, Word::Timestamped { .. }))
+        .count();
+    let duration = text.segments.iter().map(|s| s.duration).sum::<f64>();
+    let count_audio = |f: fn(&Audio) -> bool| text.segments.iter().filter(|s| f(&s.audio)).count();
+    println!("segments: {}", text.segments.len());
+    println!("words: {words} ({timestamped} timestamped)");
+    println!("duration: {duration:.2}s");
+    println!(
+        "audio: {} ref, {} window, {} embedded, {} none",
+        count_audio(|a| matches!(a, Audio::Ref(_))),
+        count_audio(|a| matches!(a, Audio::Window(_))),
+        count_audio(|a| matches!(a, Audio::Wav(_))),
+        count_audio(|a| matches!(a, Audio::None)),
+    );
+
+    if args.segments {
+        for (i, segment) in text.segments.iter().enumerate() {
+            let audio = match &segment.audio {
+                Audio::None => "none".to_string(),
+                Audio::Wav(_) => "embedded".to_string(),
+                Audio::Ref(r) => r.clone(),
+                Audio::Window(w) => format!("{}@{:.2}", w.file, w.start),
+            };
+            let content = segment
+                .words
+                .iter()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!("{i:>5} {:>8.2}s {audio:<12} {content}", segment.duration);
+        }
+    }
+    Ok(())
+}
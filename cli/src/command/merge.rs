@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use crate::{Error, document};
+
+/// Concatenate the segments of text documents into one document.
+///
+/// The metadata is taken from the first document, relative refs are
+/// rewritten to stay valid from the folder of the output.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    #[arg(required = true, num_args = 2..)]
+    documents: Vec<PathBuf>,
+    #[arg(short, long)]
+    out: PathBuf,
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Error> {
+    let out_dir = document::dir(&args.out);
+
+    let mut merged = None;
+    for path in &args.documents {
+        let mut text = document::read(path)?;
+        document::rebase(&mut text, &document::dir(path), &out_dir)?;
+        match &mut merged {
+            None => merged = Some(text),
+            Some(merged) => merged.segments.append(&mut text.segments),
+        }
+    }
+
+    match merged {
+        Some(mut merged) => {
+            merged.format_version = common::FORMAT_VERSION;
+            document::write(&args.out, &merged)
+        }
+        None => Err(Error::Usage("no documents to merge".to_string())),
+    }
+}
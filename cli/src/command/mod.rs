@@ -0,0 +1,8 @@
+pub(crate) mod align;
+pub(crate) mod build;
+pub(crate) mod export;
+pub(crate) mod import;
+pub(crate) mod inspect;
+pub(crate) mod merge;
+pub(crate) mod split;
+pub(crate) mod validate;
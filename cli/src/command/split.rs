@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use common::Text;
+
+use crate::{Error, document};
+
+/// Split a text document into parts.
+///
+/// Parts are written as `<name>-<n>.json` to the output folder, relative refs
+/// are rewritten to stay valid from there.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    document: PathBuf,
+    /// Folder to write the parts to.
+    #[arg(short, long)]
+    out: PathBuf,
+    /// Number of segments per part.
+    #[arg(long, required_unless_present = "at", conflicts_with = "at")]
+    every: Option<usize>,
+    /// Segment indices to start new parts at.
+    #[arg(long, value_delimiter = ',')]
+    at: Vec<usize>,
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Error> {
+    let mut text = document::read(&args.document)?;
+    document::rebase(&mut text, &document::dir(&args.document), &args.out)?;
+
+    let len = text.segments.len();
+    let mut starts = match args.every {
+        Some(0) => return Err(Error::Usage("--every must be at least 1".to_string())),
+        Some(every) => (0..len).step_by(every).collect(),
+        None => args.at.clone(),
+    };
+    starts.retain(|s| *s > 0 && *s < len);
+    starts.insert(0, 0);
+    starts.push(len);
+    starts.sort();
+    starts.dedup();
+
+    let name = args
+        .document
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let parts = starts.len() - 1;
+    for (n, range) in starts.windows(2).enumerate() {
+        let mut metadata = text.metadata.clone();
+        if let Some(title) = &mut metadata.title {
+            *title = format!("{title} ({}/{parts})", n + 1);
+        }
+        let part = Text {
+            format_version: common::FORMAT_VERSION,
+            metadata,
+            segments: text.segments[range[0]..range[1]].to_vec(),
+        };
+        document::write(&args.out.join(format!("{name}-{}.json", n + 1)), &part)?;
+    }
+    Ok(())
+}
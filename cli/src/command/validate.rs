@@ -0,0 +1,78 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use common::{Audio, Severity};
+
+use crate::{Error, document};
+
+/// Check text documents for content that plays back or highlights
+/// incorrectly, including embedded audio that doesn't decode and audio refs
+/// to missing files.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    #[arg(required = true)]
+    documents: Vec<PathBuf>,
+    /// Also fail on warnings.
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Error> {
+    let fail_at = if args.deny_warnings {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+
+    let mut failed = 0;
+    for path in &args.documents {
+        let text = match document::read(path) {
+            Ok(text) => text,
+            Err(e) => {
+                println!("error: {e}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        let mut worst = None;
+        let mut diagnostics = common::validate(&text);
+        diagnostics.extend(common::validate_audio(&text));
+        diagnostics.sort_by_key(|d| d.segment);
+        for d in diagnostics {
+            println!("{}: {d}", path.display());
+            worst = worst.max(Some(d.severity()));
+        }
+
+        // not part of common::validate, which also runs in the browser
+        let dir = document::dir(path);
+        // files shared by several segments are reported once
+        let mut missing = BTreeSet::new();
+        for (i, segment) in text.segments.iter().enumerate() {
+            let reference = match &segment.audio {
+                Audio::Ref(reference) => reference,
+                Audio::Window(window) => &window.file,
+                Audio::None | Audio::Wav(_) => continue,
+            };
+            if let Some(file) = document::resolve(&dir, reference)
+                && !file.is_file()
+                && missing.insert(file.clone())
+            {
+                println!(
+                    "{}: error in segment {i}: audio file {} not found",
+                    path.display(),
+                    file.display()
+                );
+                worst = Some(Severity::Error);
+            }
+        }
+
+        if worst.is_some_and(|w| w >= fail_at) {
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::Invalid(failed));
+    }
+    Ok(())
+}
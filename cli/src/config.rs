@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use common::align::{self, Recognized};
+use serde::Deserialize;
+
+use crate::{Error, step::Step};
+
+const DEFAULT_CONFIG: &str = "literacy.toml";
+
+/// External programs used by `literacy build`, read from `literacy.toml`.
+#[derive(Deserialize, Clone, Debug)]
+pub(crate) struct Config {
+    /// Synthesizes `{text}` (also in `{text_file}`) to the wav file `{output}`.
+    pub tts: Option<Step>,
+    /// Encodes the wav file `{input}` to `{output}`, the wav is used as is
+    /// without an encoder.
+    pub encoder: Option<Encoder>,
+    /// Prints the duration of the audio file `{input}` in seconds.
+    #[serde(default = "default_duration")]
+    pub duration: Step,
+    /// Aligns `{text_file}` with the wav file `{audio}`.
+    pub aligner: Option<Aligner>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub(crate) struct Encoder {
+    #[serde(flatten)]
+    pub step: Step,
+    pub extension: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub(crate) struct Aligner {
+    #[serde(flatten)]
+    pub step: Step,
+    /// File the aligner writes its result to, stdout is used if unset.
+    pub output: Option<String>,
+    #[serde(default)]
+    pub format: AlignmentFormat,
+}
+
+#[derive(Deserialize, ValueEnum, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum AlignmentFormat {
+    /// A json array of `{ "content", "start", "end" }` words.
+    #[default]
+    Words,
+    /// The json result of WhisperX.
+    Whisperx,
+    /// The json result of ctc-forced-aligner.
+    CtcForcedAligner,
+}
+
+impl AlignmentFormat {
+    pub(crate) fn parse(self, json: &str) -> Result<Vec<Recognized>, serde_json::Error> {
+        match self {
+            AlignmentFormat::Words => align::words(json),
+            AlignmentFormat::Whisperx => align::whisperx(json),
+            AlignmentFormat::CtcForcedAligner => align::ctc_forced_aligner(json),
+        }
+    }
+}
+
+fn default_duration() -> Step {
+    Step {
+        command: [
+            "ffprobe",
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            "{input}",
+        ]
+        .map(String::from)
+        .to_vec(),
+        stdin: None,
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tts: None,
+            encoder: None,
+            duration: default_duration(),
+            aligner: None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config at `path`, or `./literacy.toml` if it exists.
+    pub(crate) fn load(path: Option<&Path>) -> Result<Self, Error> {
+        let path = match path {
+            Some(p) => p.to_path_buf(),
+            None if Path::new(DEFAULT_CONFIG).exists() => PathBuf::from(DEFAULT_CONFIG),
+            None => return Ok(Self::default()),
+        };
+        let content = std::fs::read_to_string(&path).map_err(Error::io(&path))?;
+        toml::from_str(&content).map_err(|source| Error::Config { path, source })
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::{Component, Path, PathBuf};
+
+use common::{Audio, Text};
+
+use crate::Error;
+
+pub(crate) fn read(path: &Path) -> Result<Text, Error> {
+    let json = std::fs::read_to_string(path).map_err(Error::io(path))?;
+    common::migrate::from_str(&json).map_err(|source| Error::Document {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub(crate) fn write(path: &Path, text: &Text) -> Result<(), Error> {
+    if let Some(dir) = path.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(dir).map_err(Error::io(dir))?;
+    }
+    let json = serde_json::to_string_pretty(text)?;
+    std::fs::write(path, json).map_err(Error::io(path))
+}
+
+/// Folder relative refs of the document at `path` are resolved against.
+pub(crate) fn dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Local file of a ref, `None` for urls and server absolute refs.
+pub(crate) fn resolve(dir: &Path, reference: &str) -> Option<PathBuf> {
+    if reference.starts_with('/') || reference.contains("://") {
+        None
+    } else {
+        Some(dir.join(reference))
+    }
+}
+
+/// Rewrites the relative refs of `text` from being relative to the folder
+/// `from` to being relative to the folder `to`.
+pub(crate) fn rebase(text: &mut Text, from: &Path, to: &Path) -> Result<(), Error> {
+    let from = absolute(from)?;
+    let to = absolute(to)?;
+    if from == to {
+        return Ok(());
+    }
+    let rebase_ref = |reference: &mut String| {
+        if let Some(file) = resolve(&from, reference) {
+            *reference = relative(&to, &file);
+        }
+    };
+    for segment in &mut text.segments {
+        match &mut segment.audio {
+            Audio::Ref(reference) => rebase_ref(reference),
+            Audio::Window(window) => rebase_ref(&mut window.file),
+            Audio::None | Audio::Wav(_) => {}
+        }
+    }
+    if let Some(cover) = &mut text.metadata.cover {
+        rebase_ref(cover);
+    }
+    Ok(())
+}
+
+fn absolute(path: &Path) -> Result<PathBuf, Error> {
+    let path = std::path::absolute(path).map_err(Error::io(path))?;
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+    Ok(normalized)
+}
+
+fn relative(dir: &Path, file: &Path) -> String {
+    let file = absolute(file).unwrap_or_else(|_| file.to_path_buf());
+    let common = dir
+        .components()
+        .zip(file.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let up = dir.components().count() - common;
+    std::iter::repeat_n("..".to_string(), up)
+        .chain(
+            file.components()
+                .skip(common)
+                .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,628 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{Seek, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::{Audio, Block, MarkKind, Segment, Text, Word};
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{Error, document};
+
+const ACTIVE_CLASS: &str = "-epub-media-overlay-active";
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+const STYLE: &str = r#"body { line-height: 1.5; }
+.-epub-media-overlay-active { background-color: #ffe58a; }
+"#;
+
+/// Audio of one segment inside the package.
+struct SegmentAudio {
+    href: String,
+    media_type: &'static str,
+    /// `None` if the file is shared with an earlier segment.
+    bytes: Option<Vec<u8>>,
+    /// Start of the segment in the file in seconds.
+    offset: f64,
+}
+
+/// Writes `text` as an EPUB 3 package with a SMIL media overlay.
+///
+/// Timestamped words are synced one by one, segments of raw words as a whole.
+/// Segment audio is read from the folder `dir` of the document or decoded
+/// from the document, segments without audio are not synced. Files shared by
+/// several segments are added once.
+pub(crate) fn write(text: &Text, dir: &Path, out: impl Write + Seek) -> Result<(), Error> {
+    let mut text = text.clone();
+    for segment in &mut text.segments {
+        segment.make_timestamps_relative();
+    }
+    let text = &text;
+    let mut shared = BTreeMap::new();
+    let audio = text
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| segment_audio(&s.audio, i, dir, &mut shared))
+        .collect::<Result<Vec<_>, _>>()?;
+    let cover = match &text.metadata.cover {
+        Some(cover) => {
+            let file = document::resolve(dir, cover)
+                .ok_or_else(|| Error::Usage(format!("cover {cover} is not a local file")))?;
+            let bytes = std::fs::read(&file).map_err(Error::io(&file))?;
+            let extension = match extension(cover) {
+                e if e.is_empty() => image_extension(image_type(&bytes)).to_string(),
+                e => e,
+            };
+            Some((format!("cover.{extension}"), bytes))
+        }
+        None => None,
+    };
+
+    let mut zip = ZipWriter::new(out);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut add = |name: &str, bytes: &[u8], options: SimpleFileOptions| -> Result<(), Error> {
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(bytes).map_err(Error::io(name))
+    };
+
+    // the mimetype has to come first and uncompressed
+    add("mimetype", b"application/epub+zip", stored)?;
+    add("META-INF/container.xml", CONTAINER.as_bytes(), deflated)?;
+    add(
+        "OEBPS/content.opf",
+        package(text, &audio, cover.as_ref().map(|c| c.0.as_str())).as_bytes(),
+        deflated,
+    )?;
+    add("OEBPS/nav.xhtml", nav(text).as_bytes(), deflated)?;
+    add("OEBPS/text.xhtml", content(text).as_bytes(), deflated)?;
+    add(
+        "OEBPS/text.smil",
+        overlay(text, &audio).as_bytes(),
+        deflated,
+    )?;
+    add("OEBPS/style.css", STYLE.as_bytes(), deflated)?;
+    if let Some((href, bytes)) = &cover {
+        add(&format!("OEBPS/{href}"), bytes, stored)?;
+    }
+    for a in audio.iter().flatten() {
+        if let Some(bytes) = &a.bytes {
+            add(&format!("OEBPS/{}", a.href), bytes, stored)?;
+        }
+    }
+
+    zip.finish().map_err(zip_error)?;
+    Ok(())
+}
+
+fn zip_error(e: zip::result::ZipError) -> Error {
+    Error::Usage(format!("writing epub failed: {e}"))
+}
+
+/// Audio of the segment at `index`, `shared` holds the hrefs of the files
+/// windows were already cut from.
+fn segment_audio(
+    audio: &Audio,
+    index: usize,
+    dir: &Path,
+    shared: &mut BTreeMap<PathBuf, String>,
+) -> Result<Option<SegmentAudio>, Error> {
+    let local = |reference: &str| {
+        document::resolve(dir, reference).ok_or_else(|| {
+            Error::Usage(format!("segment {index}: {reference} is not a local file"))
+        })
+    };
+    let (extension, bytes) = match audio {
+        Audio::None => return Ok(None),
+        Audio::Wav(wav) => {
+            let bytes = wav
+                .decode()
+                .map_err(|e| Error::Usage(format!("segment {index}: {e}")))?;
+            // despite the name any format may be embedded
+            (audio_extension(&bytes).to_string(), bytes)
+        }
+        Audio::Ref(reference) => {
+            let file = local(reference)?;
+            let bytes = std::fs::read(&file).map_err(Error::io(&file))?;
+            (extension(reference), bytes)
+        }
+        Audio::Window(window) => {
+            let file = local(&window.file)?;
+            let extension = extension(&window.file);
+            let media_type = audio_media_type(&extension);
+            if let Some(href) = shared.get(&file) {
+                return Ok(Some(SegmentAudio {
+                    href: href.clone(),
+                    media_type,
+                    bytes: None,
+                    offset: window.start,
+                }));
+            }
+            let bytes = std::fs::read(&file).map_err(Error::io(&file))?;
+            let href = format!("audio/{index}.{extension}");
+            shared.insert(file, href.clone());
+            return Ok(Some(SegmentAudio {
+                href,
+                media_type,
+                bytes: Some(bytes),
+                offset: window.start,
+            }));
+        }
+    };
+    Ok(Some(SegmentAudio {
+        href: format!("audio/{index}.{extension}"),
+        media_type: audio_media_type(&extension),
+        bytes: Some(bytes),
+        offset: 0.0,
+    }))
+}
+
+fn extension(reference: &str) -> String {
+    Path::new(reference)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn audio_media_type(extension: &str) -> &'static str {
+    match extension {
+        "mp3" => "audio/mpeg",
+        "m4a" | "mp4" | "aac" => "audio/mp4",
+        "ogg" | "oga" | "opus" => "audio/ogg",
+        "webm" => "audio/webm",
+        "flac" => "audio/flac",
+        _ => "audio/wav",
+    }
+}
+
+/// Extension of the audio format `bytes` start like, wav if unknown.
+fn audio_extension(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [b'I', b'D', b'3', ..] => "mp3",
+        // adts frames of aac, mpeg audio frames otherwise
+        [0xff, b, ..] if b & 0xf6 == 0xf0 => "aac",
+        [0xff, b, ..] if b & 0xe0 == 0xe0 => "mp3",
+        [b'O', b'g', b'g', b'S', ..] => "ogg",
+        [b'f', b'L', b'a', b'C', ..] => "flac",
+        [0x1a, 0x45, 0xdf, 0xa3, ..] => "webm",
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => "m4a",
+        _ => "wav",
+    }
+}
+
+fn image_media_type(extension: &str) -> &'static str {
+    match extension {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+/// Media type of the image format `bytes` start like, jpeg if unknown.
+fn image_type(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'E',
+            b'B',
+            b'P',
+            ..,
+        ] => "image/webp",
+        _ if bytes.trim_ascii_start().starts_with(b"<") => "image/svg+xml",
+        _ => "image/jpeg",
+    }
+}
+
+fn image_extension(media_type: &str) -> &'static str {
+    match media_type {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        _ => "jpg",
+    }
+}
+
+fn package(text: &Text, audio: &[Option<SegmentAudio>], cover: Option<&str>) -> String {
+    let metadata = &text.metadata;
+    let title = metadata.title.as_deref().unwrap_or("Untitled");
+    let language = metadata.language.as_deref().unwrap_or("und");
+    let duration = text
+        .segments
+        .iter()
+        .zip(audio)
+        .filter(|(_, a)| a.is_some())
+        .map(|(s, _)| s.duration)
+        .sum::<f64>();
+
+    let mut opf = String::new();
+    _ = write!(
+        opf,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id" xml:lang="{language}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">urn:literacy:{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:language>{}</dc:language>
+"#,
+        escape(&identifier(text)),
+        escape(title),
+        escape(language),
+    );
+    if let Some(author) = &metadata.author {
+        _ = writeln!(opf, "    <dc:creator>{}</dc:creator>", escape(author));
+    }
+    if let Some(source) = &metadata.source {
+        _ = writeln!(opf, "    <dc:source>{}</dc:source>", escape(source));
+    }
+    if let Some(license) = &metadata.license {
+        _ = writeln!(opf, "    <dc:rights>{}</dc:rights>", escape(license));
+    }
+    _ = write!(
+        opf,
+        r##"    <meta property="dcterms:modified">{}</meta>
+    <meta property="media:duration" refines="#text-overlay">{}</meta>
+    <meta property="media:duration">{}</meta>
+    <meta property="media:active-class">{ACTIVE_CLASS}</meta>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="text" href="text.xhtml" media-type="application/xhtml+xml" media-overlay="text-overlay"/>
+    <item id="text-overlay" href="text.smil" media-type="application/smil+xml"/>
+    <item id="style" href="style.css" media-type="text/css"/>
+"##,
+        modified(),
+        clock(duration),
+        clock(duration),
+    );
+    if let Some(cover) = cover {
+        _ = writeln!(
+            opf,
+            r#"    <item id="cover" href="{cover}" media-type="{}" properties="cover-image"/>"#,
+            image_media_type(&extension(cover))
+        );
+    }
+    for (i, a) in audio.iter().enumerate() {
+        if let Some(a) = a
+            && a.bytes.is_some()
+        {
+            _ = writeln!(
+                opf,
+                r#"    <item id="audio-{i}" href="{}" media-type="{}"/>"#,
+                a.href, a.media_type
+            );
+        }
+    }
+    opf.push_str(
+        r#"  </manifest>
+  <spine>
+    <itemref idref="text"/>
+  </spine>
+</package>
+"#,
+    );
+    opf
+}
+
+/// Table of contents with the title and the headings of the text.
+fn nav(text: &Text) -> String {
+    let title = escape(text.metadata.title.as_deref().unwrap_or("Untitled"));
+    let mut entries = format!("      <li><a href=\"text.xhtml\">{title}</a></li>\n");
+    for span in common::blocks(&text.segments) {
+        if let Block::Heading(_) = span.block {
+            let heading = span
+                .segments
+                .clone()
+                .flat_map(|i| text.segments[i].words.iter().map(String::from))
+                .collect::<Vec<_>>()
+                .join(" ");
+            _ = writeln!(
+                entries,
+                r#"      <li><a href="text.xhtml#s{}">{}</a></li>"#,
+                span.segments.start,
+                escape(&heading)
+            );
+        }
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>{title}</title></head>
+<body>
+  <nav epub:type="toc">
+    <ol>
+{entries}    </ol>
+  </nav>
+</body>
+</html>
+"#
+    )
+}
+
+fn content(text: &Text) -> String {
+    let title = escape(text.metadata.title.as_deref().unwrap_or("Untitled"));
+    let language = escape(text.metadata.language.as_deref().unwrap_or("und"));
+    let mut xhtml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xml:lang="{language}" lang="{language}">
+<head>
+  <title>{title}</title>
+  <link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+"#
+    );
+    // tag of the list currently open
+    let mut list: Option<&str> = None;
+    for span in common::blocks(&text.segments) {
+        let (list_tag, open, close) = match span.block {
+            Block::Paragraph => (None, "<p>".to_string(), "</p>".to_string()),
+            Block::Heading(level) => {
+                let level = level.clamp(1, 6);
+                (None, format!("<h{level}>"), format!("</h{level}>"))
+            }
+            Block::ListItem { ordered } => (
+                Some(if ordered { "ol" } else { "ul" }),
+                "<li>".to_string(),
+                "</li>".to_string(),
+            ),
+            Block::Quote => (
+                None,
+                "<blockquote><p>".to_string(),
+                "</p></blockquote>".to_string(),
+            ),
+        };
+        if list != list_tag {
+            if let Some(tag) = list {
+                _ = writeln!(xhtml, "  </{tag}>");
+            }
+            if let Some(tag) = list_tag {
+                _ = writeln!(xhtml, "  <{tag}>");
+            }
+            list = list_tag;
+        }
+        _ = writeln!(xhtml, "  {open}");
+        for i in span.segments {
+            _ = writeln!(
+                xhtml,
+                r#"    <span id="s{i}">{}</span>"#,
+                words(&text.segments[i], i)
+            );
+        }
+        _ = writeln!(xhtml, "  {close}");
+    }
+    if let Some(tag) = list {
+        _ = writeln!(xhtml, "  </{tag}>");
+    }
+    xhtml.push_str("</body>\n</html>\n");
+    xhtml
+}
+
+fn words(segment: &Segment, index: usize) -> String {
+    let mut xhtml = String::new();
+    for (k, w) in segment.words.iter().enumerate() {
+        let marks = segment.marks_at(k);
+        for mark in &marks {
+            xhtml.push_str(mark_tag(*mark).0);
+        }
+        _ = write!(
+            xhtml,
+            r#"<span id="s{index}w{k}">{}</span>"#,
+            escape(&String::from(w))
+        );
+        for mark in marks.iter().rev() {
+            xhtml.push_str(mark_tag(*mark).1);
+        }
+        if segment.line_break_after(k) {
+            xhtml.push_str("<br/>");
+        } else if k + 1 < segment.words.len() {
+            xhtml.push(' ');
+        }
+    }
+    xhtml
+}
+
+fn mark_tag(kind: MarkKind) -> (&'static str, &'static str) {
+    match kind {
+        MarkKind::Emphasis => ("<em>", "</em>"),
+        MarkKind::Strong => ("<strong>", "</strong>"),
+        MarkKind::Code => ("<code>", "</code>"),
+    }
+}
+
+fn overlay(text: &Text, audio: &[Option<SegmentAudio>]) -> String {
+    let mut smil = r#"<?xml version="1.0" encoding="UTF-8"?>
+<smil xmlns="http://www.w3.org/ns/SMIL" xmlns:epub="http://www.idpf.org/2007/ops" version="3.0">
+  <body>
+    <seq id="seq" epub:textref="text.xhtml" epub:type="bodymatter">
+"#
+    .to_string();
+    for (i, (segment, audio)) in text.segments.iter().zip(audio).enumerate() {
+        let Some(audio) = audio else {
+            continue;
+        };
+        let timestamped = segment
+            .words
+            .iter()
+            .all(|w| matches!(w, Word::Timestamped { .. }));
+        if timestamped && !segment.words.is_empty() {
+            for (k, word) in segment.words.iter().enumerate() {
+                if let Word::Timestamped { start, end, .. } = word {
+                    par(
+                        &mut smil,
+                        &format!("s{i}w{k}"),
+                        &audio.href,
+                        audio.offset + start,
+                        audio.offset + end,
+                    );
+                }
+            }
+        } else {
+            par(
+                &mut smil,
+                &format!("s{i}"),
+                &audio.href,
+                audio.offset,
+                audio.offset + segment.duration,
+            );
+        }
+    }
+    smil.push_str("    </seq>\n  </body>\n</smil>\n");
+    smil
+}
+
+fn par(smil: &mut String, id: &str, audio: &str, start: f64, end: f64) {
+    _ = write!(
+        smil,
+        r#"      <par id="p-{id}">
+        <text src="text.xhtml#{id}"/>
+        <audio src="{audio}" clipBegin="{}" clipEnd="{}"/>
+      </par>
+"#,
+        clock(start),
+        clock(end.max(start)),
+    );
+}
+
+/// Formats seconds as a SMIL clock value `hh:mm:ss.mmm`.
+fn clock(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Stable identifier from the title and words of the text.
+fn identifier(text: &Text) -> String {
+    // FNV-1a, only needs to be stable between exports of the same text
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let title = text.metadata.title.iter().map(|t| t.as_str());
+    let words = text
+        .segments
+        .iter()
+        .flat_map(|s| &s.words)
+        .map(|w| match w {
+            Word::Raw(content) | Word::Timestamped { content, .. } => content.as_str(),
+        });
+    for part in title.chain(words) {
+        for byte in part.bytes().chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+/// Current time as `CCYY-MM-DDThh:mm:ssZ`.
+fn modified() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_audio_format_from_magic_bytes() {
+        let formats: [(&[u8], &str); 8] = [
+            (b"ID3\x04\x00", "mp3"),
+            (&[0xff, 0xfb, 0x90, 0x64], "mp3"),
+            (&[0xff, 0xf1, 0x50, 0x80], "aac"),
+            (b"OggS\x00\x02", "ogg"),
+            (b"fLaC\x00", "flac"),
+            (&[0x1a, 0x45, 0xdf, 0xa3, 0x01], "webm"),
+            (b"\x00\x00\x00\x20ftypM4A ", "m4a"),
+            (b"RIFF\x24\x00\x00\x00WAVEfmt ", "wav"),
+        ];
+        for (bytes, extension) in formats {
+            assert_eq!(audio_extension(bytes), extension);
+        }
+        assert_eq!(audio_media_type(audio_extension(b"OggS")), "audio/ogg");
+    }
+
+    #[test]
+    fn cover_without_extension_gets_one_from_its_content() {
+        let dir = std::env::temp_dir().join(format!("literacy-epub-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cover"), b"\x89PNG\r\n\x1a\n").unwrap();
+        let text = Text {
+            format_version: common::FORMAT_VERSION,
+            metadata: common::Metadata {
+                cover: Some("cover".to_string()),
+                ..Default::default()
+            },
+            segments: vec![],
+        };
+        let mut epub = std::io::Cursor::new(vec![]);
+        let written = write(&text, &dir, &mut epub);
+        std::fs::remove_dir_all(&dir).unwrap();
+        written.unwrap();
+
+        let mut epub = zip::ZipArchive::new(epub).unwrap();
+        assert!(epub.by_name("OEBPS/cover.png").is_ok());
+        let mut package = String::new();
+        std::io::Read::read_to_string(
+            &mut epub.by_name("OEBPS/content.opf").unwrap(),
+            &mut package,
+        )
+        .unwrap();
+        assert!(package.contains(r#"href="cover.png" media-type="image/png""#));
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use common::migrate::MigrationError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub(crate) enum Error {
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {source}", path.display())]
+    Document {
+        path: PathBuf,
+        source: MigrationError,
+    },
+    #[error("{}: {source}", path.display())]
+    Config {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("{step} step failed: {message}")]
+    Step { step: &'static str, message: String },
+    #[error("validation failed for {0} document(s)")]
+    Invalid(usize),
+    #[error("{0}")]
+    Usage(String),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.into();
+        move |source| Error::Io { path, source }
+    }
+}
@@ -0,0 +1,53 @@
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+mod command;
+mod config;
+mod document;
+mod epub;
+mod error;
+mod markdown;
+mod step;
+
+pub(crate) use error::Error;
+
+/// Build, inspect and maintain literacy text documents.
+#[derive(Parser)]
+#[command(name = "literacy", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Align(command::align::Args),
+    Build(Box<command::build::Args>),
+    Export(command::export::Args),
+    Import(command::import::Args),
+    Inspect(command::inspect::Args),
+    Validate(command::validate::Args),
+    Merge(command::merge::Args),
+    Split(command::split::Args),
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Align(args) => command::align::run(args),
+        Command::Build(args) => command::build::run(*args),
+        Command::Export(args) => command::export::run(args),
+        Command::Import(args) => command::import::run(args),
+        Command::Inspect(args) => command::inspect::run(args),
+        Command::Validate(args) => command::validate::run(args),
+        Command::Merge(args) => command::merge::run(args),
+        Command::Split(args) => command::split::run(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use common::{Block, Mark, MarkKind};
+
+/// A sentence of the input and the structure it carries, indices are of its
+/// words.
+#[derive(Default)]
+pub(crate) struct Sentence {
+    pub(crate) words: Vec<String>,
+    /// Block the sentence starts, if it is the first of one.
+    pub(crate) block: Option<Block>,
+    pub(crate) marks: Vec<Mark>,
+    pub(crate) line_breaks: Vec<usize>,
+}
+
+impl Sentence {
+    /// The sentence without formatting, as it is read.
+    pub(crate) fn text(&self) -> String {
+        self.words.join(" ")
+    }
+}
+
+/// Reads the subset of Markdown that maps onto the text format.
+///
+/// Blank lines separate paragraphs, `#` starts headings, `-`, `*`, `+` and
+/// `1.` start list items and `>` quotes. `*emphasis*`, `**strong**` and
+/// `` `code` `` are recognized at word boundaries, two trailing spaces or a
+/// trailing `\` break the line. Blocks are split into sentences at words
+/// ending in `.`, `!`, `?` or `:`.
+pub(crate) fn sentences(input: &str) -> Vec<Sentence> {
+    blocks(input)
+        .into_iter()
+        .flat_map(|(block, lines)| split(block, &lines))
+        .collect()
+}
+
+/// A line of a block and whether it ends with a hard line break.
+type Line<'a> = (&'a str, bool);
+
+fn blocks(input: &str) -> Vec<(Block, Vec<Line<'_>>)> {
+    let mut blocks: Vec<(Block, Vec<Line>)> = vec![];
+    // whether the last block continues on the next line
+    let mut open = false;
+    for line in input.lines() {
+        let hard_break = line.ends_with("  ") || line.trim_end().ends_with('\\');
+        let trimmed = line.trim().trim_end_matches('\\').trim_end();
+        if trimmed.is_empty() {
+            open = false;
+            continue;
+        }
+        if let Some((level, rest)) = heading(trimmed) {
+            blocks.push((Block::Heading(level), vec![(rest, false)]));
+            open = false;
+        } else if let Some((ordered, rest)) = list_item(trimmed) {
+            blocks.push((Block::ListItem { ordered }, vec![(rest, hard_break)]));
+            open = true;
+        } else if let Some(rest) = trimmed.strip_prefix('>') {
+            let rest = (rest.trim_start(), hard_break);
+            match blocks.last_mut() {
+                Some((Block::Quote, lines)) if open => lines.push(rest),
+                _ => blocks.push((Block::Quote, vec![rest])),
+            }
+            open = true;
+        } else if open && let Some((_, lines)) = blocks.last_mut() {
+            lines.push((trimmed, hard_break));
+        } else {
+            blocks.push((Block::Paragraph, vec![(trimmed, hard_break)]));
+            open = true;
+        }
+    }
+    blocks
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    Some((level as u8, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            return Some((false, rest.trim_start()));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let rest = line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "));
+    match rest {
+        Some(rest) if digits > 0 => Some((true, rest.trim_start())),
+        _ => None,
+    }
+}
+
+const MARKERS: [(&str, MarkKind); 5] = [
+    ("**", MarkKind::Strong),
+    ("__", MarkKind::Strong),
+    ("*", MarkKind::Emphasis),
+    ("_", MarkKind::Emphasis),
+    ("`", MarkKind::Code),
+];
+
+/// Punctuation that may follow a closing marker, e.g. in `*word*.`.
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', ')', '"', '\'', '”', '’'];
+
+fn split(block: Block, lines: &[Line]) -> Vec<Sentence> {
+    let mut words = vec![];
+    let mut marks = vec![];
+    let mut line_breaks = vec![];
+    let mut open: Vec<(MarkKind, usize)> = vec![];
+    for (line, hard_break) in lines {
+        for token in line.split_whitespace() {
+            let index = words.len();
+            let mut word = token;
+            while let Some((marker, kind)) = MARKERS
+                .iter()
+                .find(|(m, _)| word.len() > m.len() && word.starts_with(m))
+            {
+                word = &word[marker.len()..];
+                if !open.iter().any(|(k, _)| k == kind) {
+                    open.push((*kind, index));
+                }
+            }
+            let trailing = word.len() - word.trim_end_matches(TRAILING).len();
+            let (mut core, punctuation) = word.split_at(word.len() - trailing);
+            while let Some((marker, kind)) = MARKERS
+                .iter()
+                .find(|(m, _)| core.len() > m.len() && core.ends_with(m))
+            {
+                core = &core[..core.len() - marker.len()];
+                if let Some(position) = open.iter().position(|(k, _)| k == kind) {
+                    let (kind, start) = open.remove(position);
+                    marks.push(Mark {
+                        kind,
+                        start,
+                        end: index + 1,
+                    });
+                }
+            }
+            words.push(format!("{core}{punctuation}"));
+        }
+        if *hard_break && !words.is_empty() {
+            line_breaks.push(words.len() - 1);
+        }
+    }
+    // unclosed marks end with the block
+    for (kind, start) in open {
+        marks.push(Mark {
+            kind,
+            start,
+            end: words.len(),
+        });
+    }
+
+    let mut sentences = vec![];
+    let mut start = 0;
+    for (i, word) in words.iter().enumerate() {
+        if word.ends_with(['.', '!', '?', ':']) || i + 1 == words.len() {
+            let range = start..i + 1;
+            sentences.push(Sentence {
+                words: words[range.clone()].to_vec(),
+                block: (start == 0).then_some(block),
+                marks: marks
+                    .iter()
+                    .filter(|m| m.start < range.end && m.end > range.start)
+                    .map(|m| Mark {
+                        kind: m.kind,
+                        start: m.start.max(range.start) - range.start,
+                        end: m.end.min(range.end) - range.start,
+                    })
+                    .collect(),
+                line_breaks: line_breaks
+                    .iter()
+                    .filter(|b| range.contains(b))
+                    .map(|b| b - range.start)
+                    .collect(),
+            });
+            start = i + 1;
+        }
+    }
+    sentences
+}
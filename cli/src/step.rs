@@ -0,0 +1,111 @@
+use std::{
+    collections::BTreeMap,
+    io::{ErrorKind, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+use serde::Deserialize;
+
+use crate::Error;
+
+/// An external program run for one step of a build.
+///
+/// `{name}` placeholders in the command and stdin are replaced with the
+/// variables of the invocation, e.g. `{text}` or `{output}`.
+#[derive(Deserialize, Clone, Debug)]
+pub(crate) struct Step {
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub stdin: Option<String>,
+}
+
+#[derive(Default, Clone, Debug)]
+pub(crate) struct Vars(BTreeMap<&'static str, String>);
+
+impl Vars {
+    pub(crate) fn with(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.0.insert(name, value.to_string());
+        self
+    }
+
+    /// Replaces the placeholders in one pass, so braces in the values are
+    /// kept as they are. Unknown placeholders are left in place.
+    pub(crate) fn expand(&self, template: &str) -> String {
+        let mut expanded = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            expanded.push_str(&rest[..open]);
+            rest = &rest[open..];
+            let value = rest
+                .find('}')
+                .and_then(|close| Some((self.0.get(&rest[1..close])?, close)));
+            match value {
+                Some((value, close)) => {
+                    expanded.push_str(value);
+                    rest = &rest[close + 1..];
+                }
+                None => {
+                    expanded.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+}
+
+impl Step {
+    /// Runs the program and returns its stdout.
+    pub(crate) fn run(&self, name: &'static str, vars: &Vars) -> Result<String, Error> {
+        let failed = |message: String| Error::Step {
+            step: name,
+            message,
+        };
+
+        let mut args = self.command.iter().map(|a| vars.expand(a));
+        let program = args
+            .next()
+            .ok_or_else(|| failed("command is empty".to_string()))?;
+        let mut child = Command::new(&program)
+            .args(args)
+            .stdin(if self.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| failed(format!("{program}: {e}")))?;
+        // written from a thread, the program may fill its stdout before it
+        // read all of stdin
+        let writer = match (&self.stdin, child.stdin.take()) {
+            (Some(stdin), Some(mut pipe)) => {
+                let stdin = vars.expand(stdin);
+                Some(thread::spawn(move || pipe.write_all(stdin.as_bytes())))
+            }
+            _ => None,
+        };
+        let output = child
+            .wait_with_output()
+            .map_err(|e| failed(format!("{program}: {e}")))?;
+        if !output.status.success() {
+            return Err(failed(format!(
+                "{program} exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        match writer.map(|w| w.join()) {
+            Some(Err(_)) => return Err(failed(format!("{program}: writing stdin panicked"))),
+            // the program is done, it didn't need the rest of its input
+            Some(Ok(Err(e))) if e.kind() != ErrorKind::BrokenPipe => {
+                return Err(failed(format!("{program}: {e}")));
+            }
+            _ => {}
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
@@ -0,0 +1,181 @@
+//! Runs the commands with stub steps made of `sh` and `cat`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use common::{Audio, Text, Word};
+
+/// Folder removed again when the test is done.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("literacy-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn join(&self, path: &str) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn literacy(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_literacy"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[track_caller]
+fn success(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn read(path: &Path) -> Text {
+    common::migrate::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// The tts writes the text it gets on stdin as "audio" and echoes it.
+const CONFIG: &str = r#"
+[tts]
+command = ["sh", "-c", "tee {output}"]
+stdin = "{text}"
+
+[duration]
+command = ["sh", "-c", "echo 10"]
+"#;
+
+/// Gives every word a second.
+const ALIGNER: &str = r#"
+[aligner]
+command = ["sh", "-c", "cat {text_file} | tr ' ' '\\n' | awk 'BEGIN { printf \"[\" } { if (NR > 1) printf \",\"; printf \"{\\\"content\\\":\\\"%s\\\",\\\"start\\\":%d,\\\"end\\\":%d}\", $0, NR - 1, NR } END { print \"]\" }'"]
+"#;
+
+fn build(dir: &TempDir, config: &str, input: &str) {
+    fs::write(dir.join("literacy.toml"), config).unwrap();
+    fs::write(dir.join("input.txt"), input).unwrap();
+    success(literacy(
+        &dir.0,
+        &["build", "input.txt", "--out", "book", "--title", "Buch"],
+    ));
+}
+
+#[test]
+fn build_runs_the_steps() {
+    let dir = TempDir::new("build");
+    build(
+        &dir,
+        &format!("{CONFIG}{ALIGNER}"),
+        "Guten Tag. Wie geht es {output}?\n",
+    );
+
+    let text = read(&dir.join("book/text.json"));
+    assert_eq!(text.metadata.title.as_deref(), Some("Buch"));
+    assert_eq!(text.segments.len(), 2);
+    assert_eq!(text.segments[0].duration, 10.0);
+    assert!(matches!(&text.segments[0].audio, Audio::Ref(r) if r == "0.wav"));
+    assert!(matches!(
+        &text.segments[1].words[..],
+        [.., Word::Timestamped { content, start, end }]
+            if content == "{output}?" && *start == 3.0 && *end == 4.0
+    ));
+    // placeholders in the text are passed on as they are
+    assert_eq!(
+        fs::read_to_string(dir.join("book/1.wav")).unwrap(),
+        "Wie geht es {output}?"
+    );
+}
+
+#[test]
+fn build_passes_long_texts_through_stdin() {
+    let dir = TempDir::new("long");
+    // more than a pipe buffer, which the tts echoes while reading it
+    let sentence = "Wort ".repeat(40_000);
+    build(&dir, CONFIG, &sentence);
+    assert_eq!(
+        fs::read_to_string(dir.join("book/0.wav")).unwrap(),
+        sentence.trim()
+    );
+}
+
+#[test]
+fn failing_steps_are_reported() {
+    let dir = TempDir::new("failing");
+    fs::write(
+        dir.join("literacy.toml"),
+        "[tts]\ncommand = [\"sh\", \"-c\", \"echo kaputt >&2; exit 3\"]\n",
+    )
+    .unwrap();
+    fs::write(dir.join("input.txt"), "Hallo.").unwrap();
+    let output = literacy(&dir.0, &["build", "input.txt", "--out", "book"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("tts step failed"), "{stderr}");
+    assert!(stderr.contains("kaputt"), "{stderr}");
+}
+
+#[test]
+fn validate_reports_missing_audio() {
+    let dir = TempDir::new("validate");
+    build(&dir, CONFIG, "Eins. Zwei.");
+    success(literacy(&dir.0, &["validate", "book/text.json"]));
+
+    fs::remove_file(dir.join("book/1.wav")).unwrap();
+    let output = literacy(&dir.0, &["validate", "book/text.json"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("segment 1: audio file"), "{stdout}");
+}
+
+#[test]
+fn split_and_merge_keep_refs_valid() {
+    let dir = TempDir::new("split");
+    build(&dir, CONFIG, "Eins. Zwei. Drei.");
+
+    success(literacy(
+        &dir.0,
+        &["split", "book/text.json", "--out", "parts", "--every", "2"],
+    ));
+    let first = read(&dir.join("parts/text-1.json"));
+    let second = read(&dir.join("parts/text-2.json"));
+    assert_eq!(first.segments.len(), 2);
+    assert_eq!(second.segments.len(), 1);
+    assert_eq!(second.metadata.title.as_deref(), Some("Buch (2/2)"));
+    assert!(matches!(&second.segments[0].audio, Audio::Ref(r) if r == "../book/2.wav"));
+
+    success(literacy(
+        &dir.0,
+        &[
+            "merge",
+            "parts/text-1.json",
+            "parts/text-2.json",
+            "--out",
+            "merged/text.json",
+        ],
+    ));
+    let merged = read(&dir.join("merged/text.json"));
+    let original = read(&dir.join("book/text.json"));
+    assert_eq!(merged.segments.len(), 3);
+    let words =
+        |text: &Text| -> Vec<String> { text.segments[2].words.iter().map(String::from).collect() };
+    assert_eq!(words(&merged), words(&original));
+    assert!(matches!(&merged.segments[2].audio, Audio::Ref(r) if r == "../book/2.wav"));
+    success(literacy(&dir.0, &["validate", "merged/text.json"]));
+}
@@ -0,0 +1,10 @@
+//! Prints the JSON Schema of the text document format.
+//!
+//! `cargo run -p common --features schema --example schema > schema/text.schema.json`
+
+fn main() {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&common::json_schema()).expect("schema is valid json")
+    );
+}
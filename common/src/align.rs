@@ -0,0 +1,619 @@
+use std::{fmt, ops::RangeInclusive};
+
+use serde::Deserialize;
+
+use crate::Word;
+
+/// Cost of an original word without a recognized word, a match costs the
+/// normalized edit distance of the words.
+const MISSING_COST: f64 = 0.5;
+/// Cost of a recognized word without an original word.
+const FALSELY_DETECTED_COST: f64 = 0.5;
+/// Extra cost of matching two words with one, keeps one to one matches
+/// preferred unless the combined words match clearly better.
+const MERGE_COST: f64 = 0.35;
+/// How many recognized words a match may lie off the position proportional
+/// to the original word, bounds the alignment to a band around the diagonal.
+const BAND: usize = 64;
+
+/// A word as recognized by a speech recognizer or aligner.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Recognized {
+    pub content: String,
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    /// Confidence of the recognizer in `0.0..=1.0`.
+    pub score: Option<f64>,
+}
+
+/// Reads the `word_segments` of a WhisperX json result.
+pub fn whisperx(json: &str) -> Result<Vec<Recognized>, serde_json::Error> {
+    #[derive(Deserialize)]
+    struct Output {
+        word_segments: Vec<WordSegment>,
+    }
+
+    #[derive(Deserialize)]
+    struct WordSegment {
+        word: String,
+        start: Option<f64>,
+        end: Option<f64>,
+        score: Option<f64>,
+    }
+
+    let output: Output = serde_json::from_str(json)?;
+    Ok(output
+        .word_segments
+        .into_iter()
+        .map(|w| Recognized {
+            content: w.word,
+            start: w.start,
+            end: w.end,
+            score: w.score,
+        })
+        .collect())
+}
+
+/// Reads the `segments` of a ctc-forced-aligner json result.
+pub fn ctc_forced_aligner(json: &str) -> Result<Vec<Recognized>, serde_json::Error> {
+    #[derive(Deserialize)]
+    struct Output {
+        segments: Vec<Segment>,
+    }
+
+    #[derive(Deserialize)]
+    struct Segment {
+        text: String,
+        start: Option<f64>,
+        end: Option<f64>,
+        score: Option<f64>,
+    }
+
+    let output: Output = serde_json::from_str(json)?;
+    Ok(output
+        .segments
+        .into_iter()
+        .map(|s| Recognized {
+            content: s.text,
+            start: s.start,
+            end: s.end,
+            score: s.score,
+        })
+        .collect())
+}
+
+/// Reads a json array of `{ "content", "start", "end" }` words.
+pub fn words(json: &str) -> Result<Vec<Recognized>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+    /// Matched one recognized word.
+    Matched,
+    /// Matched one recognized word together with a neighbouring original word.
+    Merged,
+    /// Matched two recognized words.
+    Split,
+    /// Not recognized, timestamps are interpolated from the neighbours.
+    Interpolated,
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchKind::Matched => write!(f, "matched"),
+            MatchKind::Merged => write!(f, "merged"),
+            MatchKind::Split => write!(f, "split"),
+            MatchKind::Interpolated => write!(f, "interpolated"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordReport {
+    pub content: String,
+    /// Recognized words the original word was matched with.
+    pub recognized: Vec<String>,
+    pub kind: MatchKind,
+    /// Similarity of the words weighted with the recognizer score, in
+    /// `0.0..=1.0`, `0.0` for interpolated words.
+    pub confidence: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Alignment {
+    /// The original words with timestamps, one for every original word.
+    pub words: Vec<Word>,
+    /// One report for every original word.
+    pub report: Vec<WordReport>,
+    /// Recognized words that match no original word.
+    pub falsely_detected: Vec<Recognized>,
+}
+
+#[derive(Clone, Copy, Default)]
+enum Step {
+    #[default]
+    Match,
+    Missing,
+    FalselyDetected,
+    Merge,
+    Split,
+}
+
+/// Aligns recognized words to the original words of a text.
+///
+/// Words are compared by normalized edit distance, ignoring case, accents
+/// and punctuation. An original word may also match two recognized words or
+/// two original words one recognized word, for words the recognizer split
+/// or merged. Original words without usable timestamps get timestamps
+/// interpolated between their neighbours, `duration` bounds the last ones.
+///
+/// Only matches within [`BAND`] words of the proportional position are
+/// considered, recognized words shifted further count as falsely detected.
+pub fn align(original: &[String], recognized: &[Recognized], duration: f64) -> Alignment {
+    let o: Vec<String> = original.iter().map(|w| normalize(w)).collect();
+    let r: Vec<String> = recognized.iter().map(|w| normalize(&w.content)).collect();
+    let (n, m) = (o.len(), r.len());
+
+    let band = Band::new(n, m);
+    let mut cost = band.table(f64::INFINITY);
+    let mut step = band.table(Step::default());
+    cost.set(0, 0, 0.0);
+    for i in 0..=n {
+        for j in band.columns(i) {
+            let mut candidates = vec![];
+            if i > 0 && j > 0 {
+                candidates.push((
+                    cost.get(i - 1, j - 1) + distance(&o[i - 1], &r[j - 1]),
+                    Step::Match,
+                ));
+            }
+            if i > 0 {
+                candidates.push((cost.get(i - 1, j) + MISSING_COST, Step::Missing));
+            }
+            if j > 0 {
+                candidates.push((
+                    cost.get(i, j - 1) + FALSELY_DETECTED_COST,
+                    Step::FalselyDetected,
+                ));
+            }
+            if i > 1 && j > 0 {
+                candidates.push((
+                    cost.get(i - 2, j - 1) + merged_distance(&o[i - 2], &o[i - 1], &r[j - 1]),
+                    Step::Merge,
+                ));
+            }
+            if i > 0 && j > 1 {
+                candidates.push((
+                    cost.get(i - 1, j - 2) + merged_distance(&r[j - 2], &r[j - 1], &o[i - 1]),
+                    Step::Split,
+                ));
+            }
+            if let Some((c, s)) = candidates.into_iter().min_by(|a, b| a.0.total_cmp(&b.0)) {
+                cost.set(i, j, c);
+                step.set(i, j, s);
+            }
+        }
+    }
+
+    let mut times: Vec<Option<(f64, f64)>> = vec![None; n];
+    let mut report: Vec<WordReport> = original
+        .iter()
+        .map(|w| WordReport {
+            content: w.clone(),
+            recognized: vec![],
+            kind: MatchKind::Interpolated,
+            confidence: 0.0,
+        })
+        .collect();
+    let mut falsely_detected = vec![];
+
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        match step.get(i, j) {
+            Step::Match => {
+                let w = &recognized[j - 1];
+                times[i - 1] = timestamps(w.start, w.end);
+                report[i - 1].recognized = vec![w.content.clone()];
+                report[i - 1].kind = MatchKind::Matched;
+                report[i - 1].confidence = confidence(&o[i - 1], &r[j - 1], &[w]);
+                i -= 1;
+                j -= 1;
+            }
+            Step::Missing => i -= 1,
+            Step::FalselyDetected => {
+                falsely_detected.push(recognized[j - 1].clone());
+                j -= 1;
+            }
+            Step::Merge => {
+                let w = &recognized[j - 1];
+                let merged = format!("{}{}", o[i - 2], o[i - 1]);
+                let confidence = confidence(&merged, &r[j - 1], &[w]);
+                if let Some((start, end)) = timestamps(w.start, w.end) {
+                    // divide the time by the length of the original words
+                    let first = o[i - 2].chars().count().max(1) as f64;
+                    let second = o[i - 1].chars().count().max(1) as f64;
+                    let middle = start + (end - start) * first / (first + second);
+                    times[i - 2] = Some((start, middle));
+                    times[i - 1] = Some((middle, end));
+                }
+                for k in [i - 2, i - 1] {
+                    report[k].recognized = vec![w.content.clone()];
+                    report[k].kind = MatchKind::Merged;
+                    report[k].confidence = confidence;
+                }
+                i -= 2;
+                j -= 1;
+            }
+            Step::Split => {
+                let (a, b) = (&recognized[j - 2], &recognized[j - 1]);
+                let split = format!("{}{}", r[j - 2], r[j - 1]);
+                times[i - 1] = timestamps(a.start.or(b.start), b.end.or(a.end));
+                report[i - 1].recognized = vec![a.content.clone(), b.content.clone()];
+                report[i - 1].kind = MatchKind::Split;
+                report[i - 1].confidence = confidence(&o[i - 1], &split, &[a, b]);
+                i -= 1;
+                j -= 2;
+            }
+        }
+    }
+    falsely_detected.reverse();
+
+    for (k, t) in times.iter().enumerate() {
+        if t.is_none() {
+            report[k].kind = MatchKind::Interpolated;
+            report[k].confidence = 0.0;
+        }
+    }
+
+    let times = interpolate(original, &times, duration);
+    let words = original
+        .iter()
+        .zip(times)
+        .map(|(content, (start, end))| Word::Timestamped {
+            content: content.clone(),
+            start,
+            end,
+        })
+        .collect();
+
+    Alignment {
+        words,
+        report,
+        falsely_detected,
+    }
+}
+
+/// Cells of the alignment table within [`BAND`] of the diagonal, so long
+/// texts take time and memory linear in their length.
+struct Band {
+    rows: Vec<RangeInclusive<usize>>,
+}
+
+impl Band {
+    fn new(n: usize, m: usize) -> Self {
+        // wide enough that neighbouring rows overlap when there are many
+        // more recognized than original words
+        let width = BAND + m.div_ceil(n.max(1));
+        let rows = (0..=n)
+            .map(|i| {
+                let center = (i * m).checked_div(n).unwrap_or(0);
+                center.saturating_sub(width)..=(center + width).min(m)
+            })
+            .collect();
+        Self { rows }
+    }
+
+    fn columns(&self, i: usize) -> RangeInclusive<usize> {
+        self.rows[i].clone()
+    }
+
+    fn table<T: Copy>(&self, value: T) -> Table<T> {
+        Table {
+            rows: self
+                .rows
+                .iter()
+                .map(|r| (*r.start(), vec![value; r.clone().count()]))
+                .collect(),
+            outside: value,
+        }
+    }
+}
+
+/// Values of the cells of a [`Band`], `outside` for the cells outside it.
+struct Table<T> {
+    rows: Vec<(usize, Vec<T>)>,
+    outside: T,
+}
+
+impl<T: Copy> Table<T> {
+    fn get(&self, i: usize, j: usize) -> T {
+        let (start, row) = &self.rows[i];
+        j.checked_sub(*start)
+            .and_then(|k| row.get(k))
+            .copied()
+            .unwrap_or(self.outside)
+    }
+
+    fn set(&mut self, i: usize, j: usize, value: T) {
+        let (start, row) = &mut self.rows[i];
+        row[j - *start] = value;
+    }
+}
+
+fn timestamps(start: Option<f64>, end: Option<f64>) -> Option<(f64, f64)> {
+    match (start, end) {
+        (Some(s), Some(e)) if s.is_finite() && e.is_finite() && s <= e => Some((s, e)),
+        _ => None,
+    }
+}
+
+fn confidence(original: &str, recognized: &str, words: &[&Recognized]) -> f64 {
+    let score = words
+        .iter()
+        .map(|w| w.score.unwrap_or(1.0).clamp(0.0, 1.0))
+        .fold(1.0, f64::min);
+    (1.0 - distance(original, recognized)) * score
+}
+
+/// Fills missing timestamps by dividing the gap between the known neighbours
+/// by the length of the words.
+fn interpolate(
+    original: &[String],
+    times: &[Option<(f64, f64)>],
+    duration: f64,
+) -> Vec<(f64, f64)> {
+    let mut result = Vec::with_capacity(times.len());
+    let mut k = 0;
+    while k < times.len() {
+        if let Some(t) = times[k] {
+            result.push(t);
+            k += 1;
+            continue;
+        }
+        let run_end = (k..times.len())
+            .find(|i| times[*i].is_some())
+            .unwrap_or(times.len());
+        let from = result.last().map(|t: &(f64, f64)| t.1).unwrap_or(0.0);
+        let to = times
+            .get(run_end)
+            .copied()
+            .flatten()
+            .map(|t| t.0)
+            .unwrap_or(duration.max(from));
+        let lengths: Vec<f64> = original[k..run_end]
+            .iter()
+            .map(|w| w.chars().count().max(1) as f64)
+            .collect();
+        let total: f64 = lengths.iter().sum();
+        let mut start = from;
+        for length in lengths {
+            let end = start + (to - from).max(0.0) * length / total;
+            result.push((start, end));
+            start = end;
+        }
+        k = run_end;
+    }
+    result
+}
+
+/// Lowercases, folds common accents and drops everything but letters and
+/// digits.
+fn normalize(word: &str) -> String {
+    let mut normalized = String::with_capacity(word.len());
+    for c in word.chars().flat_map(char::to_lowercase) {
+        match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => normalized.push('a'),
+            'ç' | 'č' => normalized.push('c'),
+            'è' | 'é' | 'ê' | 'ë' | 'ē' => normalized.push('e'),
+            'ì' | 'í' | 'î' | 'ï' | 'ī' => normalized.push('i'),
+            'ñ' => normalized.push('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => normalized.push('o'),
+            'ù' | 'ú' | 'û' | 'ü' | 'ū' => normalized.push('u'),
+            'š' => normalized.push('s'),
+            'ž' => normalized.push('z'),
+            'æ' => normalized.push_str("ae"),
+            'œ' => normalized.push_str("oe"),
+            'ß' => normalized.push_str("ss"),
+            c if c.is_alphanumeric() => normalized.push(c),
+            _ => {}
+        }
+    }
+    normalized
+}
+
+/// Levenshtein distance divided by the length of the longer word.
+fn distance(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 0.0;
+    }
+    levenshtein(a, b) as f64 / longest as f64
+}
+
+/// Cost of matching the words `a` and `b` together with `other`.
+///
+/// The edit distance is divided by the length of the shorter part, so a
+/// short word can't be merged into a long one for free.
+fn merged_distance(a: &str, b: &str, other: &str) -> f64 {
+    let shortest = a.chars().count().min(b.chars().count()).max(1);
+    let edits = levenshtein(&format!("{a}{b}"), other);
+    (edits as f64 / shortest as f64).min(1.0) + MERGE_COST
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHISPERX: &str = r#"{
+        "segments": [{ "start": 0.0, "end": 3.2, "text": "Heute äh gehen nachhause dann Haus aufgaben" }],
+        "word_segments": [
+            { "word": "Heute", "start": 0.0, "end": 0.4, "score": 0.95 },
+            { "word": "äh", "start": 0.4, "end": 0.6, "score": 0.3 },
+            { "word": "gehen", "start": 0.6, "end": 0.9, "score": 0.9 },
+            { "word": "nachhause", "start": 1.2, "end": 1.92, "score": 0.8 },
+            { "word": "dann", "start": 2.0, "end": 2.2 },
+            { "word": "Haus", "start": 2.3, "end": 2.6, "score": 0.9 },
+            { "word": "aufgaben", "start": 2.6, "end": 3.2, "score": 0.7 }
+        ]
+    }"#;
+
+    const CTC_FORCED_ALIGNER: &str = r#"{
+        "text": "Guten Tack Welt",
+        "segments": [
+            { "start": 0.1, "end": 0.5, "text": "Guten" },
+            { "start": 0.5, "end": 0.8, "text": "Tack", "score": 0.9 },
+            { "start": 0.9, "end": 1.3, "text": "Welt" }
+        ]
+    }"#;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    fn times(alignment: &Alignment) -> Vec<(f64, f64)> {
+        alignment
+            .words
+            .iter()
+            .map(|w| match w {
+                Word::Timestamped { start, end, .. } => (*start, *end),
+                Word::Raw(_) => panic!("untimed word"),
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn whisperx_merged_split_and_false_words() {
+        let recognized = whisperx(WHISPERX).unwrap();
+        let original = words("Heute gehen wir nach Hause, dann Hausaufgaben.");
+        let alignment = align(&original, &recognized, 4.0);
+
+        let kinds: Vec<_> = alignment.report.iter().map(|r| r.kind).collect();
+        use MatchKind::*;
+        assert_eq!(
+            kinds,
+            [
+                Matched,
+                Matched,
+                Interpolated,
+                Merged,
+                Merged,
+                Matched,
+                Split
+            ]
+        );
+        let t = times(&alignment);
+        assert_close(t[0], (0.0, 0.4));
+        // the gap between the neighbours
+        assert_close(t[2], (0.9, 1.2));
+        // divided by the length of "nach" and "hause"
+        assert_close(t[3], (1.2, 1.52));
+        assert_close(t[4], (1.52, 1.92));
+        assert_close(t[6], (2.3, 3.2));
+        assert_eq!(alignment.report[6].recognized, ["Haus", "aufgaben"]);
+        assert_eq!(
+            alignment
+                .falsely_detected
+                .iter()
+                .map(|w| w.content.as_str())
+                .collect::<Vec<_>>(),
+            ["äh"]
+        );
+    }
+
+    #[test]
+    fn confidence_weighs_similarity_with_the_score() {
+        let recognized = whisperx(WHISPERX).unwrap();
+        let original = words("Heute gehen wir nach Hause, dann Hausaufgaben.");
+        let report = align(&original, &recognized, 4.0).report;
+
+        assert!((report[0].confidence - 0.95).abs() < 1e-9);
+        assert_eq!(report[2].confidence, 0.0);
+        assert!((report[3].confidence - 0.8).abs() < 1e-9);
+        assert_eq!(report[3].confidence, report[4].confidence);
+        // without a score the similarity counts alone
+        assert_eq!(report[5].confidence, 1.0);
+        // the lower score of both parts
+        assert!((report[6].confidence - 0.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ctc_forced_aligner_misrecognized_word() {
+        let recognized = ctc_forced_aligner(CTC_FORCED_ALIGNER).unwrap();
+        let alignment = align(&words("Guten Tag, Welt!"), &recognized, 2.0);
+
+        assert!(
+            alignment
+                .report
+                .iter()
+                .all(|r| r.kind == MatchKind::Matched)
+        );
+        // "tag" and "tack" differ in two of four letters
+        assert!((alignment.report[1].confidence - 0.45).abs() < 1e-9);
+        let t = times(&alignment);
+        assert_close(t[1], (0.5, 0.8));
+        assert_close(t[2], (0.9, 1.3));
+        assert!(alignment.falsely_detected.is_empty());
+    }
+
+    #[test]
+    fn unrecognized_words_at_the_end_run_to_the_duration() {
+        let recognized = super::words(r#"[{ "content": "eins", "start": 0.0, "end": 1.0 }]"#);
+        let recognized = recognized.unwrap();
+        let alignment = align(&words("eins zwei drei"), &recognized, 3.0);
+        let t = times(&alignment);
+        assert_close(t[1], (1.0, 2.0));
+        assert_close(t[2], (2.0, 3.0));
+    }
+
+    #[test]
+    fn long_texts_align_within_the_band() {
+        let original: Vec<String> = (0..1000).map(|k| format!("wort{k}")).collect();
+        // noise at the start shifts every word off the diagonal
+        let recognized: Vec<Recognized> = (0..40)
+            .map(|k| format!("rauschen{k}"))
+            .chain(original.iter().cloned())
+            .enumerate()
+            .map(|(k, content)| Recognized {
+                content,
+                start: Some(k as f64),
+                end: Some(k as f64 + 0.5),
+                score: None,
+            })
+            .collect();
+        let alignment = align(&original, &recognized, 1500.0);
+
+        assert!(
+            alignment
+                .report
+                .iter()
+                .all(|r| r.kind == MatchKind::Matched)
+        );
+        assert_eq!(alignment.falsely_detected.len(), 40);
+        assert_close(times(&alignment)[999], (1039.0, 1039.5));
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Text;
+
+/// A word in a text.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub segment: usize,
+    pub word: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Color {
+    Yellow,
+    Green,
+    Blue,
+    Pink,
+}
+
+impl Color {
+    pub const ALL: [Color; 4] = [Color::Yellow, Color::Green, Color::Blue, Color::Pink];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Color::Yellow => "yellow",
+            Color::Green => "green",
+            Color::Blue => "blue",
+            Color::Pink => "pink",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Bookmark,
+    Highlight(Color),
+}
+
+/// A bookmark or highlight of the words `start..=end` of a text.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub kind: Kind,
+    pub start: Location,
+    pub end: Location,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub created: u64,
+}
+
+impl Annotation {
+    pub fn contains(&self, location: Location) -> bool {
+        (self.start..=self.end).contains(&location)
+    }
+
+    /// The annotated words joined by spaces.
+    pub fn excerpt(&self, text: &Text) -> String {
+        let mut words = vec![];
+        for (s, segment) in text.segments.iter().enumerate() {
+            for (w, word) in segment.words.iter().enumerate() {
+                if self.contains(Location {
+                    segment: s,
+                    word: w,
+                }) {
+                    words.push(word.content());
+                }
+            }
+        }
+        words.join(" ")
+    }
+}
+
+/// Lists annotations as Markdown, in the order they appear in the text.
+///
+/// Segment numbers are one based like the reader shows them.
+pub fn to_markdown(text: &Text, annotations: &[Annotation]) -> String {
+    let title = text.metadata.title.as_deref().unwrap_or("Untitled");
+    let mut markdown = format!("# Annotations of {title}\n\n");
+    let mut annotations: Vec<&Annotation> = annotations.iter().collect();
+    annotations.sort_by_key(|a| (a.start, a.end));
+    for a in annotations {
+        let kind = match a.kind {
+            Kind::Bookmark => "Bookmark".to_string(),
+            Kind::Highlight(color) => format!("Highlight ({})", color.name()),
+        };
+        _ = write!(
+            markdown,
+            "- {kind}, segment {}: “{}”",
+            a.start.segment + 1,
+            a.excerpt(text)
+        );
+        if let Some(note) = &a.note {
+            _ = write!(markdown, " — {note}");
+        }
+        markdown.push('\n');
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{FORMAT_VERSION, Metadata, Segment, Word};
+
+    fn text(title: Option<&str>) -> Text {
+        let segment = |words: &str| Segment {
+            words: words.split(' ').map(|w| Word::Raw(w.into())).collect(),
+            ..Default::default()
+        };
+        Text {
+            format_version: FORMAT_VERSION,
+            metadata: Metadata {
+                title: title.map(String::from),
+                ..Default::default()
+            },
+            segments: vec![segment("Es war einmal"), segment("ein kleines Haus.")],
+        }
+    }
+
+    fn annotation(kind: Kind, start: (usize, usize), end: (usize, usize)) -> Annotation {
+        let location = |(segment, word)| Location { segment, word };
+        Annotation {
+            kind,
+            start: location(start),
+            end: location(end),
+            note: None,
+            created: 0,
+        }
+    }
+
+    #[test]
+    fn annotations_round_trip() {
+        let value = json!({
+            "kind": { "highlight": "pink" },
+            "start": { "segment": 0, "word": 2 },
+            "end": { "segment": 1, "word": 0 },
+            "note": "Anfang",
+            "created": 1_700_000_000_000u64,
+        });
+        let parsed: Annotation = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(parsed.kind, Kind::Highlight(Color::Pink));
+        assert_eq!(parsed.note.as_deref(), Some("Anfang"));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), value);
+
+        // bookmarks without note or creation time
+        let value = json!({
+            "kind": "bookmark",
+            "start": { "segment": 1, "word": 1 },
+            "end": { "segment": 1, "word": 1 },
+        });
+        let parsed: Annotation = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, annotation(Kind::Bookmark, (1, 1), (1, 1)));
+        assert_eq!(serde_json::to_value(&parsed).unwrap()["created"], json!(0));
+    }
+
+    #[test]
+    fn annotations_span_segments() {
+        let a = annotation(Kind::Highlight(Color::Yellow), (0, 2), (1, 1));
+        let at = |segment, word| a.contains(Location { segment, word });
+        assert!(!at(0, 1));
+        assert!(at(0, 2) && at(1, 0) && at(1, 1));
+        assert!(!at(1, 2));
+        assert_eq!(a.excerpt(&text(None)), "einmal ein kleines");
+    }
+
+    #[test]
+    fn markdown_lists_annotations_in_text_order() {
+        let mut noted = annotation(Kind::Highlight(Color::Green), (1, 2), (1, 2));
+        noted.note = Some("Wohnort".into());
+        let annotations = [noted, annotation(Kind::Bookmark, (0, 0), (0, 1))];
+        assert_eq!(
+            to_markdown(&text(Some("Märchen")), &annotations),
+            "# Annotations of Märchen\n\n\
+             - Bookmark, segment 1: “Es war”\n\
+             - Highlight (green), segment 2: “Haus.” — Wohnort\n"
+        );
+        assert_eq!(
+            to_markdown(&text(None), &[]),
+            "# Annotations of Untitled\n\n"
+        );
+    }
+
+    #[test]
+    fn color_names_match_serialization() {
+        for color in Color::ALL {
+            assert_eq!(serde_json::to_value(color).unwrap(), json!(color.name()));
+        }
+    }
+}
@@ -1,26 +1,191 @@
 use serde::{Deserialize, Serialize};
 
+pub mod align;
+pub mod annotation;
+pub mod migrate;
+#[cfg(feature = "schema")]
+mod schema;
+mod structure;
+pub use structure::{BlockSpan, blocks};
+pub mod subtitle;
+mod timeline;
+#[cfg(feature = "schema")]
+pub use schema::json_schema;
+pub use timeline::Timeline;
+mod validate;
+pub use validate::{Diagnostic, Issue, Severity, validate, validate_audio};
+mod wav;
+pub use wav::WavError;
+
+/// Version of the text document format written by this crate.
+pub const FORMAT_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LibraryEntry {
+    pub id: String,
+    #[serde(default)]
+    pub metadata: Metadata,
+}
+
+impl LibraryEntry {
+    pub fn title(&self) -> &str {
+        self.metadata.title.as_deref().unwrap_or(&self.id)
+    }
+}
+
+/// Reading position in a text.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub segment: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word: Option<u32>,
+    /// When the position was reached, in milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub updated: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Text {
+    /// See [`migrate`] for reading documents of older versions.
+    #[serde(default)]
+    pub format_version: u32,
+    #[serde(default)]
+    pub metadata: Metadata,
     pub segments: Vec<Segment>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Metadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// BCP 47 language tag, e.g. `de` or `en-GB`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Reading level, e.g. a CEFR level like `A2`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Cover image, resolved like audio refs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<Generator>,
+    /// How the reader plays the audio, by default large files are streamed
+    /// and the others decoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playback: Option<Playback>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Playback {
+    /// Files are downloaded and decoded whole before playing, segments play
+    /// back to back without gaps and at the same loudness.
+    Decode,
+    /// Files play while they download, with range requests for the parts
+    /// played. Needs little memory, for long recordings.
+    Stream,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Generator {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Segment {
     pub words: Vec<Word>,
     pub audio: Audio,
     pub duration: f64,
+    /// Block this segment starts, segments without one continue the block of
+    /// the segment before. See [`blocks`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<Block>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub marks: Vec<Mark>,
+    /// Indices of the words followed by a line break.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub line_breaks: Vec<usize>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Block {
+    Paragraph,
+    /// Heading of level 1 to 6.
+    Heading(u8),
+    /// Item of a list, consecutive items of the same kind form one list.
+    ListItem {
+        #[serde(default)]
+        ordered: bool,
+    },
+    Quote,
+}
+
+/// Inline formatting of the words `start..end` of a segment.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Mark {
+    pub kind: MarkKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MarkKind {
+    Emphasis,
+    Strong,
+    Code,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum Audio {
+    #[default]
     None,
     Wav(Wav),
     Ref(String),
+    /// Part of a file shared with other segments, e.g. of an audiobook
+    /// recorded in one piece.
+    Window(Window),
+}
+
+/// The part `start..end` of an audio file, in seconds of the file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Window {
+    /// Resolved like audio refs.
+    pub file: String,
+    pub start: f64,
+    pub end: f64,
+    /// Whether the word timestamps of the segment are in seconds of the file
+    /// instead of the segment. See [`Segment::make_timestamps_relative`].
+    #[serde(default)]
+    pub absolute: bool,
 }
 
+/// An embedded audio file.
+///
+/// The compressed variants hold a zlib stream (RFC 1950, DEFLATE) of the file
+/// bytes, the base64 variants use the standard alphabet with padding.
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum Wav {
     Raw(Vec<u8>),
@@ -30,7 +195,8 @@ pub enum Wav {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged, deny_unknown_fields)]
 pub enum Word {
     Raw(String),
     Timestamped {
@@ -40,8 +206,47 @@ pub enum Word {
     },
 }
 
-impl Into<String> for Word {
-    fn into(self) -> String {
+impl Segment {
+    /// Start of the word at `index` in seconds. For raw words it is estimated
+    /// from the length of the words before it.
+    pub fn word_start(&self, index: usize) -> Option<f64> {
+        Timeline::new(self).start(index)
+    }
+
+    /// End of the word at `index` in seconds, estimated like
+    /// [`Segment::word_start`] for raw words.
+    pub fn word_end(&self, index: usize) -> Option<f64> {
+        Timeline::new(self).end(index)
+    }
+
+    /// Shifts word timestamps given in seconds of a shared file to seconds of
+    /// the segment, which is what playback and highlighting expect.
+    pub fn make_timestamps_relative(&mut self) {
+        let Audio::Window(window) = &mut self.audio else {
+            return;
+        };
+        if !window.absolute {
+            return;
+        }
+        for word in &mut self.words {
+            if let Word::Timestamped { start, end, .. } = word {
+                *start -= window.start;
+                *end -= window.start;
+            }
+        }
+        window.absolute = false;
+    }
+
+    /// Index of the word playing `time` seconds into the segment, the last
+    /// one starting at or before it. Build a [`Timeline`] to look up many
+    /// times.
+    pub fn word_at(&self, time: f64) -> Option<usize> {
+        Timeline::new(self).word_started(time)
+    }
+}
+
+impl Word {
+    pub fn content(&self) -> &str {
         match self {
             Word::Raw(content) => content,
             Word::Timestamped { content, .. } => content,
@@ -49,9 +254,18 @@ impl Into<String> for Word {
     }
 }
 
-impl Into<String> for &Word {
-    fn into(self) -> String {
-        match self {
+impl From<Word> for String {
+    fn from(word: Word) -> Self {
+        match word {
+            Word::Raw(content) => content,
+            Word::Timestamped { content, .. } => content,
+        }
+    }
+}
+
+impl From<&Word> for String {
+    fn from(word: &Word) -> Self {
+        match word {
             Word::Raw(content) => content.clone(),
             Word::Timestamped { content, .. } => content.clone(),
         }
@@ -0,0 +1,170 @@
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::{FORMAT_VERSION, Text};
+
+type Migration = fn(Value) -> Result<Value, MigrationError>;
+
+/// Migrations indexed by the version they upgrade from.
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("invalid document: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid format version: {0}")]
+    InvalidVersion(Value),
+    #[error("format version {0} is newer than the supported version {FORMAT_VERSION}")]
+    UnsupportedVersion(u64),
+    #[error("invalid document: expected {0}")]
+    Shape(&'static str),
+}
+
+/// Parses a text document of any known format version.
+pub fn from_str(json: &str) -> Result<Text, MigrationError> {
+    from_value(serde_json::from_str(json)?)
+}
+
+/// Converts a text document of any known format version.
+pub fn from_value(value: Value) -> Result<Text, MigrationError> {
+    Ok(serde_json::from_value(migrate(value)?)?)
+}
+
+/// Upgrades a text document of any known format version to the current one.
+///
+/// Documents without a `format_version` field are version 0.
+pub fn migrate(mut value: Value) -> Result<Value, MigrationError> {
+    let mut version = version(&value)?;
+    if version > FORMAT_VERSION as u64 {
+        return Err(MigrationError::UnsupportedVersion(version));
+    }
+    while version < FORMAT_VERSION as u64 {
+        value = MIGRATIONS[version as usize](value)?;
+        version += 1;
+        object_mut(&mut value)?.insert("format_version".to_string(), version.into());
+    }
+    Ok(value)
+}
+
+fn version(value: &Value) -> Result<u64, MigrationError> {
+    match value
+        .as_object()
+        .ok_or(MigrationError::Shape("an object"))?
+        .get("format_version")
+    {
+        None => Ok(0),
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| MigrationError::InvalidVersion(v.clone())),
+    }
+}
+
+fn object_mut(value: &mut Value) -> Result<&mut Map<String, Value>, MigrationError> {
+    value
+        .as_object_mut()
+        .ok_or(MigrationError::Shape("an object"))
+}
+
+/// Version 0 is what the nushell pipeline wrote before versioning: segments
+/// may lack `duration` and `audio`, and timestamped words carry leftovers of
+/// the alignment like `original`, `distance` or `romanized_content`.
+fn v0_to_v1(mut value: Value) -> Result<Value, MigrationError> {
+    let segments = object_mut(&mut value)?
+        .get_mut("segments")
+        .and_then(|s| s.as_array_mut())
+        .ok_or(MigrationError::Shape("a segments array"))?;
+    for segment in segments {
+        let segment = segment
+            .as_object_mut()
+            .ok_or(MigrationError::Shape("segment objects"))?;
+        let words = segment
+            .get_mut("words")
+            .and_then(|w| w.as_array_mut())
+            .ok_or(MigrationError::Shape("a words array in every segment"))?;
+        let mut end = 0.0f64;
+        for word in words {
+            if let Some(w) = word.as_object_mut() {
+                w.retain(|k, _| matches!(k.as_str(), "content" | "start" | "end"));
+                if let Some(e) = w.get("end").and_then(|e| e.as_f64()) {
+                    end = end.max(e);
+                }
+            }
+        }
+        segment.entry("audio").or_insert_with(|| "none".into());
+        segment.entry("duration").or_insert_with(|| end.into());
+    }
+    Ok(value)
+}
+
+/// Version 2 adds audio windows of files shared by several segments, which
+/// readers of version 1 can't play. Version 1 documents are valid as they are.
+fn v1_to_v2(value: Value) -> Result<Value, MigrationError> {
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Audio, Word};
+
+    #[test]
+    fn version_less_documents_are_version_0() {
+        let text = from_str(
+            r#"{
+                "metadata": {},
+                "segments": [{
+                    "words": [
+                        { "content": "Hallo", "start": 0.0, "end": 0.5, "distance": 0 },
+                        { "content": "Welt", "start": 0.5, "end": 1.25, "original": "Welt" }
+                    ]
+                }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(text.format_version, FORMAT_VERSION);
+        let segment = &text.segments[0];
+        assert!(matches!(segment.audio, Audio::None));
+        // taken from the last word
+        assert_eq!(segment.duration, 1.25);
+        assert!(
+            matches!(&segment.words[1], Word::Timestamped { content, .. } if content == "Welt")
+        );
+    }
+
+    #[test]
+    fn version_1_documents_are_kept() {
+        let json = r#"{
+            "format_version": 1,
+            "metadata": { "title": "Titel" },
+            "segments": [{ "words": ["Hallo"], "audio": { "ref": "0.wav" }, "duration": 1.0 }]
+        }"#;
+        let text = from_str(json).unwrap();
+        assert_eq!(text.format_version, FORMAT_VERSION);
+        assert_eq!(text.metadata.title.as_deref(), Some("Titel"));
+        assert!(matches!(&text.segments[0].audio, Audio::Ref(r) if r == "0.wav"));
+
+        let mut value: Value = serde_json::from_str(json).unwrap();
+        let original = value.clone();
+        object_mut(&mut value)
+            .unwrap()
+            .insert("format_version".to_string(), FORMAT_VERSION.into());
+        assert_eq!(migrate(original).unwrap(), value);
+    }
+
+    #[test]
+    fn future_versions_are_rejected() {
+        let json = format!(
+            r#"{{ "format_version": {}, "metadata": {{}}, "segments": [] }}"#,
+            FORMAT_VERSION + 1
+        );
+        assert!(matches!(
+            from_str(&json),
+            Err(MigrationError::UnsupportedVersion(v)) if v == FORMAT_VERSION as u64 + 1
+        ));
+        assert!(matches!(
+            from_str(r#"{ "format_version": "2", "segments": [] }"#),
+            Err(MigrationError::InvalidVersion(_))
+        ));
+        assert!(matches!(from_str("[]"), Err(MigrationError::Shape(_))));
+    }
+}
@@ -0,0 +1,11 @@
+use crate::Text;
+
+/// JSON Schema of the current text document format.
+pub fn json_schema() -> serde_json::Value {
+    let mut schema = schemars::schema_for!(Text);
+    schema.insert(
+        "$id".to_string(),
+        format!("literacy/text.v{}.schema.json", crate::FORMAT_VERSION).into(),
+    );
+    schema.to_value()
+}
@@ -0,0 +1,153 @@
+use std::ops::Range;
+
+use crate::{Block, MarkKind, Segment};
+
+/// A block and the segments it spans.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockSpan {
+    pub block: Block,
+    pub segments: Range<usize>,
+}
+
+/// Groups segments into the blocks they belong to.
+///
+/// Segments before the first one starting a block form a paragraph, so
+/// documents without any blocks are a single paragraph.
+pub fn blocks(segments: &[Segment]) -> Vec<BlockSpan> {
+    let mut blocks: Vec<BlockSpan> = vec![];
+    for (i, segment) in segments.iter().enumerate() {
+        match (segment.block, blocks.last_mut()) {
+            (None, Some(last)) => last.segments.end = i + 1,
+            (block, _) => blocks.push(BlockSpan {
+                block: block.unwrap_or(Block::Paragraph),
+                segments: i..i + 1,
+            }),
+        }
+    }
+    blocks
+}
+
+impl Segment {
+    /// Kinds of the marks on the word at `index`, in the order of the marks.
+    pub fn marks_at(&self, index: usize) -> Vec<MarkKind> {
+        self.marks
+            .iter()
+            .filter(|m| (m.start..m.end).contains(&index))
+            .map(|m| m.kind)
+            .collect()
+    }
+
+    /// Whether the word at `index` is followed by a line break.
+    pub fn line_break_after(&self, index: usize) -> bool {
+        self.line_breaks.contains(&index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Mark, Word};
+
+    fn segment(block: Option<Block>) -> Segment {
+        Segment {
+            words: vec![Word::Raw("Wort".into())],
+            block,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rich_text_round_trips() {
+        let value = json!({
+            "words": ["Ein", "sehr", "kurzer", "Satz."],
+            "audio": "none",
+            "duration": 0.0,
+            "block": { "list_item": { "ordered": true } },
+            "marks": [
+                { "kind": "emphasis", "start": 1, "end": 3 },
+                { "kind": "code", "start": 2, "end": 3 },
+            ],
+            "line_breaks": [1],
+        });
+        let segment: Segment = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(segment.block, Some(Block::ListItem { ordered: true }));
+        assert_eq!(
+            segment.marks[0],
+            Mark {
+                kind: MarkKind::Emphasis,
+                start: 1,
+                end: 3
+            }
+        );
+        assert_eq!(segment.marks_at(0), vec![]);
+        assert_eq!(
+            segment.marks_at(2),
+            vec![MarkKind::Emphasis, MarkKind::Code]
+        );
+        assert!(segment.line_break_after(1));
+        assert!(!segment.line_break_after(2));
+        assert_eq!(serde_json::to_value(&segment).unwrap(), value);
+    }
+
+    #[test]
+    fn blocks_serialize_in_snake_case() {
+        for (block, value) in [
+            (Block::Paragraph, json!("paragraph")),
+            (Block::Heading(2), json!({ "heading": 2 })),
+            (
+                Block::ListItem { ordered: false },
+                json!({ "list_item": { "ordered": false } }),
+            ),
+            (Block::Quote, json!("quote")),
+        ] {
+            assert_eq!(serde_json::to_value(block).unwrap(), value);
+            assert_eq!(serde_json::from_value::<Block>(value).unwrap(), block);
+        }
+        // lists are unordered unless told otherwise
+        assert_eq!(
+            serde_json::from_value::<Block>(json!({ "list_item": {} })).unwrap(),
+            Block::ListItem { ordered: false }
+        );
+    }
+
+    #[test]
+    fn plain_segments_stay_plain() {
+        let value = json!({ "words": ["Hallo"], "audio": "none", "duration": 1.0 });
+        let segment: Segment = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(segment.block, None);
+        assert!(segment.marks.is_empty() && segment.line_breaks.is_empty());
+        assert_eq!(serde_json::to_value(&segment).unwrap(), value);
+    }
+
+    #[test]
+    fn segments_continue_the_block_before() {
+        let segments = [
+            segment(None),
+            segment(Some(Block::Heading(1))),
+            segment(Some(Block::Paragraph)),
+            segment(None),
+            segment(Some(Block::Quote)),
+        ];
+        let spans = |range: &[(Block, Range<usize>)]| -> Vec<BlockSpan> {
+            range
+                .iter()
+                .map(|(block, segments)| BlockSpan {
+                    block: *block,
+                    segments: segments.clone(),
+                })
+                .collect()
+        };
+        assert_eq!(
+            blocks(&segments),
+            spans(&[
+                (Block::Paragraph, 0..1),
+                (Block::Heading(1), 1..2),
+                (Block::Paragraph, 2..4),
+                (Block::Quote, 4..5),
+            ])
+        );
+        assert_eq!(blocks(&[]), vec![]);
+    }
+}
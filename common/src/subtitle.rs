@@ -0,0 +1,458 @@
+use std::fmt::Write;
+
+use thiserror::Error;
+
+use crate::{Audio, FORMAT_VERSION, Metadata, Segment, Text, Word};
+
+#[derive(Error, Debug)]
+pub enum SubtitleError {
+    #[error("missing WEBVTT header")]
+    MissingHeader,
+    #[error("line {0}: invalid cue timing")]
+    InvalidTiming(usize),
+}
+
+/// A subtitle cue, times are in seconds from the start of the text.
+#[derive(Clone, Debug, PartialEq)]
+struct Cue {
+    start: f64,
+    end: f64,
+    /// Words with the time they start at, if known.
+    words: Vec<(String, Option<f64>)>,
+}
+
+/// Converts a text to WebVTT with one cue per segment.
+///
+/// Segments are laid out one after another by their duration. With
+/// `word_timing` timestamped words are preceded by inline timestamps.
+pub fn to_vtt(text: &Text, word_timing: bool) -> String {
+    let mut vtt = "WEBVTT\n".to_string();
+    if let Some(title) = &text.metadata.title {
+        _ = writeln!(vtt, "\nNOTE {}", title.replace("-->", "->"));
+    }
+    for (i, cue) in cues(text).iter().enumerate() {
+        _ = writeln!(
+            vtt,
+            "\nsegment-{i}\n{} --> {}",
+            timestamp(cue.start, '.'),
+            timestamp(cue.end, '.')
+        );
+        let words = cue
+            .words
+            .iter()
+            .map(|(content, start)| match start {
+                Some(s) if word_timing && *s > cue.start && *s < cue.end => {
+                    format!("<{}>{}", timestamp(*s, '.'), escape(content))
+                }
+                _ => escape(content),
+            })
+            .collect::<Vec<_>>();
+        _ = writeln!(vtt, "{}", words.join(" "));
+    }
+    vtt
+}
+
+/// Converts a text to SubRip with one cue per segment.
+pub fn to_srt(text: &Text) -> String {
+    let mut srt = String::new();
+    for (i, cue) in cues(text).iter().enumerate() {
+        let words = cue
+            .words
+            .iter()
+            .map(|(content, _)| content.as_str())
+            .collect::<Vec<_>>();
+        _ = writeln!(
+            srt,
+            "{}\n{} --> {}\n{}\n",
+            i + 1,
+            timestamp(cue.start, ','),
+            timestamp(cue.end, ','),
+            words.join(" ")
+        );
+    }
+    srt
+}
+
+/// Reads a text from WebVTT, one segment per cue.
+///
+/// Words after inline timestamps become timestamped words ending where the
+/// next word starts, segments have no audio.
+pub fn from_vtt(vtt: &str) -> Result<Text, SubtitleError> {
+    let vtt = vtt.trim_start_matches('\u{feff}');
+    if !vtt.starts_with("WEBVTT") {
+        return Err(SubtitleError::MissingHeader);
+    }
+    let cues = parse(vtt, true)?;
+    Ok(text(cues))
+}
+
+/// Reads a text from SubRip, one segment per cue.
+pub fn from_srt(srt: &str) -> Result<Text, SubtitleError> {
+    let cues = parse(srt.trim_start_matches('\u{feff}'), false)?;
+    Ok(text(cues))
+}
+
+fn cues(text: &Text) -> Vec<Cue> {
+    let mut text = text.clone();
+    for segment in &mut text.segments {
+        segment.make_timestamps_relative();
+    }
+    let mut offset = 0.0;
+    text.segments
+        .iter()
+        .map(|segment| {
+            let cue = Cue {
+                start: offset,
+                end: offset + segment.duration,
+                words: segment
+                    .words
+                    .iter()
+                    .map(|w| match w {
+                        Word::Raw(content) => (content.clone(), None),
+                        Word::Timestamped { content, start, .. } => {
+                            (content.clone(), Some(offset + start))
+                        }
+                    })
+                    .collect(),
+            };
+            offset += segment.duration;
+            cue
+        })
+        .collect()
+}
+
+fn text(cues: Vec<Cue>) -> Text {
+    let segments = cues
+        .iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            // segments play one after another, gaps between cues belong to the
+            // segment before them
+            let end = cues
+                .get(i + 1)
+                .map_or(cue.end, |next| next.start.max(cue.end));
+            let timed = cue.words.iter().any(|(_, start)| start.is_some());
+            let starts = word_starts(cue);
+            let words = cue
+                .words
+                .iter()
+                .enumerate()
+                .map(|(k, (content, _))| {
+                    if !timed {
+                        return Word::Raw(content.clone());
+                    }
+                    let start = starts[k];
+                    let word_end = starts.get(k + 1).copied().unwrap_or(cue.end).max(start);
+                    Word::Timestamped {
+                        content: content.clone(),
+                        start: start - cue.start,
+                        end: word_end - cue.start,
+                    }
+                })
+                .collect();
+            Segment {
+                words,
+                audio: Audio::None,
+                duration: end - cue.start,
+                ..Default::default()
+            }
+        })
+        .collect();
+    Text {
+        format_version: FORMAT_VERSION,
+        metadata: Metadata::default(),
+        segments,
+    }
+}
+
+/// Start of every word of a cue. Words without an inline timestamp are
+/// spread evenly between the timestamps around them, so starts never go
+/// back, words before the first timestamp start with the cue.
+fn word_starts(cue: &Cue) -> Vec<f64> {
+    let mut starts = vec![cue.start; cue.words.len()];
+    let first = cue.words.first().and_then(|(_, s)| *s);
+    let (mut from, mut time) = (0, first.unwrap_or(cue.start));
+    for k in 1..=cue.words.len() {
+        let next = match cue.words.get(k) {
+            Some((_, Some(start))) => start.max(time),
+            Some((_, None)) => continue,
+            None => cue.end.max(time),
+        };
+        for (j, start) in starts[from..k].iter_mut().enumerate() {
+            *start = time + (next - time) * j as f64 / (k - from) as f64;
+        }
+        (from, time) = (k, next);
+    }
+    starts
+}
+
+fn parse(input: &str, vtt: bool) -> Result<Vec<Cue>, SubtitleError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut cues = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if !line.contains("-->") {
+            i += 1;
+            continue;
+        }
+        let (start, end) = timing(line).ok_or(SubtitleError::InvalidTiming(i + 1))?;
+        i += 1;
+        let mut words = vec![];
+        while i < lines.len() && !lines[i].trim().is_empty() {
+            words.extend(cue_words(lines[i], vtt));
+            i += 1;
+        }
+        cues.push(Cue { start, end, words });
+    }
+    Ok(cues)
+}
+
+fn timing(line: &str) -> Option<(f64, f64)> {
+    let (start, rest) = line.split_once("-->")?;
+    // webvtt cue settings may follow the end time
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+fn cue_words(line: &str, vtt: bool) -> Vec<(String, Option<f64>)> {
+    let mut words = vec![];
+    let mut current = String::new();
+    let mut pending = None;
+    let mut chars = line.chars();
+    let mut push = |current: &mut String, pending: &mut Option<f64>| {
+        if !current.is_empty() {
+            words.push((unescape(current), pending.take()));
+            current.clear();
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                let tag: String = chars.by_ref().take_while(|c| *c != '>').collect();
+                if vtt && let Some(t) = parse_timestamp(&tag) {
+                    push(&mut current, &mut pending);
+                    pending = Some(t);
+                } else if !vtt && !is_srt_tag(&tag) {
+                    // subrip has no escaping, keep what isn't formatting
+                    current.push_str(&format!("<{tag}>"));
+                }
+            }
+            c if c.is_whitespace() => push(&mut current, &mut pending),
+            c => current.push(c),
+        }
+    }
+    push(&mut current, &mut pending);
+    words
+}
+
+fn is_srt_tag(tag: &str) -> bool {
+    let name = tag.trim_start_matches('/').split_whitespace().next();
+    matches!(name, Some("i" | "b" | "u" | "font"))
+}
+
+/// Formats seconds as `hh:mm:ss.mmm`, with `,` as separator for SubRip.
+fn timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Parses `hh:mm:ss.mmm` or `mm:ss.mmm`, with `.` or `,` as separator.
+fn parse_timestamp(s: &str) -> Option<f64> {
+    let s = s.replace(',', ".");
+    let parts: Vec<&str> = s.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (h.parse::<u64>().ok()?, m.parse::<u64>().ok()?, *s),
+        [m, s] => (0, m.parse::<u64>().ok()?, *s),
+        _ => return None,
+    };
+    let seconds: f64 = seconds.parse().ok()?;
+    Some((hours * 3600 + minutes * 60) as f64 + seconds)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timed(words: &[(&str, f64, f64)], duration: f64) -> Segment {
+        Segment {
+            words: words
+                .iter()
+                .map(|(content, start, end)| Word::Timestamped {
+                    content: content.to_string(),
+                    start: *start,
+                    end: *end,
+                })
+                .collect(),
+            duration,
+            ..Default::default()
+        }
+    }
+
+    fn timings(segment: &Segment) -> Vec<(String, f64, f64)> {
+        segment
+            .words
+            .iter()
+            .map(|w| match w {
+                Word::Timestamped {
+                    content,
+                    start,
+                    end,
+                } => (content.clone(), *start, *end),
+                Word::Raw(content) => (content.clone(), f64::NAN, f64::NAN),
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: &[(String, f64, f64)], expected: &[(&str, f64, f64)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for ((content, start, end), (c, s, e)) in actual.iter().zip(expected) {
+            assert_eq!(content, c);
+            assert!((start - s).abs() < 1e-3, "{actual:?}");
+            assert!((end - e).abs() < 1e-3, "{actual:?}");
+        }
+    }
+
+    #[test]
+    fn vtt_round_trip_keeps_word_timing() {
+        let text = Text {
+            format_version: FORMAT_VERSION,
+            metadata: Metadata::default(),
+            segments: vec![
+                timed(&[("Hallo", 0.0, 0.5), ("Welt", 0.5, 1.2)], 1.5),
+                timed(&[("a<b", 0.0, 0.25), ("&", 0.25, 0.75)], 0.75),
+            ],
+        };
+        let parsed = from_vtt(&to_vtt(&text, true)).unwrap();
+        assert_eq!(parsed.segments.len(), 2);
+        assert_close(
+            &timings(&parsed.segments[0]),
+            &[("Hallo", 0.0, 0.5), ("Welt", 0.5, 1.5)],
+        );
+        assert_close(
+            &timings(&parsed.segments[1]),
+            &[("a<b", 0.0, 0.25), ("&", 0.25, 0.75)],
+        );
+        assert!((parsed.segments[0].duration - 1.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn srt_round_trip_keeps_words_and_durations() {
+        let text = Text {
+            format_version: FORMAT_VERSION,
+            metadata: Metadata::default(),
+            segments: vec![
+                Segment {
+                    words: vec![Word::Raw("Guten".into()), Word::Raw("Tag".into())],
+                    duration: 2.0,
+                    ..Default::default()
+                },
+                timed(&[("Tschüss", 0.0, 1.0)], 1.25),
+            ],
+        };
+        let parsed = from_srt(&to_srt(&text)).unwrap();
+        let words: Vec<Vec<String>> = parsed
+            .segments
+            .iter()
+            .map(|s| s.words.iter().map(String::from).collect())
+            .collect();
+        assert_eq!(words, [vec!["Guten", "Tag"], vec!["Tschüss"]]);
+        assert!((parsed.segments[0].duration - 2.0).abs() < 1e-3);
+        assert!((parsed.segments[1].duration - 1.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn words_without_inline_timestamp_are_spread_monotonically() {
+        let vtt = "WEBVTT\n\n00:00:00.000 --> 00:00:04.000\n<00:00:01.000>a b <00:00:03.000>c\n";
+        let text = from_vtt(vtt).unwrap();
+        assert_close(
+            &timings(&text.segments[0]),
+            &[("a", 1.0, 2.0), ("b", 2.0, 3.0), ("c", 3.0, 4.0)],
+        );
+        let issues: Vec<_> = crate::validate(&text)
+            .into_iter()
+            .map(|d| d.issue)
+            .filter(|i| *i != crate::Issue::MissingAudio)
+            .collect();
+        assert_eq!(issues, []);
+    }
+
+    #[test]
+    fn words_before_the_first_timestamp_start_with_the_cue() {
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 00:00:03.000\nx y <00:00:02.000>z\n";
+        let text = from_vtt(vtt).unwrap();
+        assert_close(
+            &timings(&text.segments[0]),
+            &[("x", 0.0, 0.5), ("y", 0.5, 1.0), ("z", 1.0, 2.0)],
+        );
+    }
+
+    #[test]
+    fn timestamps_without_hours_and_with_either_separator() {
+        assert_eq!(parse_timestamp("01:02.500"), Some(62.5));
+        assert_eq!(parse_timestamp("01:01:02.500"), Some(3662.5));
+        assert_eq!(parse_timestamp("00:00:02,250"), Some(2.25));
+        assert_eq!(parse_timestamp("2.5"), None);
+
+        let vtt = "WEBVTT\n\n00:01.000 --> 00:02.500 align:start\nkurz\n";
+        let text = from_vtt(vtt).unwrap();
+        assert!((text.segments[0].duration - 1.5).abs() < 1e-9);
+
+        let srt = "1\n00:00:01,000 --> 00:00:02,500\n<i>kurz</i>\n";
+        let text = from_srt(srt).unwrap();
+        assert!((text.segments[0].duration - 1.5).abs() < 1e-9);
+        assert_eq!(String::from(&text.segments[0].words[0]), "kurz");
+    }
+
+    #[test]
+    fn absolute_windows_are_laid_out_like_other_segments() {
+        let mut window = timed(&[("x", 2.0, 2.6), ("y", 2.6, 3.5)], 2.0);
+        window.audio = Audio::Window(crate::Window {
+            file: "book.mp3".into(),
+            start: 2.0,
+            end: 4.0,
+            absolute: true,
+        });
+        let text = Text {
+            format_version: FORMAT_VERSION,
+            metadata: Metadata::default(),
+            segments: vec![timed(&[("w", 0.0, 2.0)], 2.0), window],
+        };
+        let vtt = to_vtt(&text, true);
+        assert!(
+            vtt.contains("00:00:02.000 --> 00:00:04.000\nx <00:00:02.600>y\n"),
+            "{vtt}"
+        );
+        assert_close(
+            &timings(&from_vtt(&vtt).unwrap().segments[1]),
+            &[("x", 0.0, 0.6), ("y", 0.6, 2.0)],
+        );
+    }
+
+    #[test]
+    fn vtt_needs_header() {
+        assert!(matches!(
+            from_vtt("00:00.000 --> 00:01.000\nx\n"),
+            Err(SubtitleError::MissingHeader)
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{Segment, Word};
+
+/// Times of the words of a segment in order, to find the word spoken at a
+/// time by binary search.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timeline(Vec<Span>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Span {
+    start: f64,
+    end: f64,
+    /// Times of raw words are estimated from the length of the words.
+    estimated: bool,
+}
+
+impl Timeline {
+    pub fn new(segment: &Segment) -> Self {
+        let length = |w: &Word| (w.content().chars().count() + 1) as f64;
+        let total: f64 = segment.words.iter().map(length).sum();
+        let mut before = 0.0;
+        let mut spans: Vec<Span> = segment
+            .words
+            .iter()
+            .map(|w| {
+                let estimate = segment.duration * before / total;
+                before += length(w);
+                match w {
+                    Word::Timestamped { start, end, .. } => Span {
+                        start: *start,
+                        end: *end,
+                        estimated: false,
+                    },
+                    Word::Raw(_) => Span {
+                        start: estimate,
+                        end: segment.duration,
+                        estimated: true,
+                    },
+                }
+            })
+            .collect();
+        // raw words last until the next word starts
+        for i in 1..spans.len() {
+            if spans[i - 1].estimated {
+                spans[i - 1].end = spans[i].start;
+            }
+        }
+        Self(spans)
+    }
+
+    pub fn start(&self, index: usize) -> Option<f64> {
+        self.0.get(index).map(|s| s.start)
+    }
+
+    pub fn end(&self, index: usize) -> Option<f64> {
+        self.0.get(index).map(|s| s.end)
+    }
+
+    /// Index of the last word starting at or before `time`.
+    pub fn word_started(&self, time: f64) -> Option<usize> {
+        self.0.partition_point(|s| s.start <= time).checked_sub(1)
+    }
+
+    /// Index of the timestamped word spoken `time` seconds into the segment,
+    /// `None` between words and in raw words.
+    pub fn word_at(&self, time: f64) -> Option<usize> {
+        let index = self.word_started(time)?;
+        let span = self.0[index];
+        (!span.estimated && time <= span.end).then_some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timed(content: &str, start: f64, end: f64) -> Word {
+        Word::Timestamped {
+            content: content.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn timestamped_words() {
+        let segment = Segment {
+            words: vec![timed("eins", 0.5, 1.0), timed("zwei", 1.5, 2.0)],
+            duration: 2.5,
+            ..Default::default()
+        };
+        let timeline = Timeline::new(&segment);
+        assert_eq!(timeline.word_at(0.2), None);
+        assert_eq!(timeline.word_at(0.5), Some(0));
+        assert_eq!(timeline.word_at(1.2), None);
+        assert_eq!(timeline.word_at(2.0), Some(1));
+        assert_eq!(timeline.word_started(0.2), None);
+        assert_eq!(timeline.word_started(1.2), Some(0));
+        assert_eq!(timeline.word_started(9.0), Some(1));
+    }
+
+    #[test]
+    fn raw_words_are_estimated_by_length() {
+        let segment = Segment {
+            words: vec![Word::Raw("abc".into()), Word::Raw("abcdefg".into())],
+            duration: 3.0,
+            ..Default::default()
+        };
+        let timeline = Timeline::new(&segment);
+        assert_eq!(timeline.start(1), Some(1.0));
+        assert_eq!(timeline.end(0), Some(1.0));
+        assert_eq!(timeline.end(1), Some(3.0));
+        assert_eq!(timeline.word_started(1.5), Some(1));
+        assert_eq!(timeline.word_at(1.5), None);
+        assert_eq!(segment.word_at(0.5), Some(0));
+    }
+}
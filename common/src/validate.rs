@@ -0,0 +1,458 @@
+use std::fmt;
+
+use crate::{Audio, Block, FORMAT_VERSION, Segment, Text, Word};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Issue {
+    UnsupportedVersion(u32),
+    NoSegments,
+    EmptySegment,
+    InvalidDuration(f64),
+    MissingAudio,
+    EmptyAudioRef,
+    UndecodableAudio(String),
+    InvalidAudioWindow { start: f64, end: f64 },
+    MixedWords,
+    EmptyWord,
+    InvalidTimestamp { start: f64, end: f64 },
+    EndBeforeStart { start: f64, end: f64 },
+    BeyondDuration { end: f64, duration: f64 },
+    NotMonotonic { previous_start: f64, start: f64 },
+    Overlap { previous_end: f64, start: f64 },
+    InvalidHeadingLevel(u8),
+    InvalidMark { start: usize, end: usize },
+    InvalidLineBreak(usize),
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::UnsupportedVersion(_)
+            | Issue::NoSegments
+            | Issue::InvalidDuration(_)
+            | Issue::EmptyAudioRef
+            | Issue::UndecodableAudio(_)
+            | Issue::InvalidAudioWindow { .. }
+            | Issue::InvalidTimestamp { .. }
+            | Issue::EndBeforeStart { .. }
+            | Issue::NotMonotonic { .. } => Severity::Error,
+            Issue::EmptySegment
+            | Issue::MissingAudio
+            | Issue::MixedWords
+            | Issue::EmptyWord
+            | Issue::BeyondDuration { .. }
+            | Issue::Overlap { .. }
+            | Issue::InvalidHeadingLevel(_)
+            | Issue::InvalidMark { .. }
+            | Issue::InvalidLineBreak(_) => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::UnsupportedVersion(v) => write!(
+                f,
+                "format version {v} is newer than the supported version {FORMAT_VERSION}"
+            ),
+            Issue::NoSegments => write!(f, "text has no segments"),
+            Issue::EmptySegment => write!(f, "segment has no words"),
+            Issue::InvalidDuration(d) => write!(f, "invalid duration {d}"),
+            Issue::MissingAudio => write!(f, "segment has no audio"),
+            Issue::EmptyAudioRef => write!(f, "audio ref is empty"),
+            Issue::UndecodableAudio(e) => write!(f, "embedded audio not decodable: {e}"),
+            Issue::InvalidAudioWindow { start, end } => {
+                write!(f, "invalid audio window {start}..{end}")
+            }
+            Issue::MixedWords => write!(f, "segment mixes raw and timestamped words"),
+            Issue::EmptyWord => write!(f, "word is empty"),
+            Issue::InvalidTimestamp { start, end } => {
+                write!(f, "invalid timestamp {start}..{end}")
+            }
+            Issue::EndBeforeStart { start, end } => {
+                write!(f, "end {end} is before start {start}")
+            }
+            Issue::BeyondDuration { end, duration } => {
+                write!(f, "end {end} is beyond the segment duration {duration}")
+            }
+            Issue::NotMonotonic {
+                previous_start,
+                start,
+            } => write!(
+                f,
+                "start {start} is before the start {previous_start} of the previous word"
+            ),
+            Issue::Overlap {
+                previous_end,
+                start,
+            } => write!(
+                f,
+                "start {start} overlaps the previous word ending at {previous_end}"
+            ),
+            Issue::InvalidHeadingLevel(l) => write!(f, "invalid heading level {l}"),
+            Issue::InvalidMark { start, end } => {
+                write!(f, "mark {start}..{end} is empty or beyond the words")
+            }
+            Issue::InvalidLineBreak(i) => write!(f, "line break after missing word {i}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub segment: Option<usize>,
+    pub word: Option<usize>,
+    pub issue: Issue,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.issue.severity()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.severity())?;
+        if let Some(s) = self.segment {
+            write!(f, " in segment {s}")?;
+        }
+        if let Some(w) = self.word {
+            write!(f, " word {w}")?;
+        }
+        write!(f, ": {}", self.issue)
+    }
+}
+
+/// Checks a text for content that plays back or highlights incorrectly.
+///
+/// Segment and word indices are zero based. Only the document itself is
+/// checked, cheap enough for the reader to run on every load: embedded audio
+/// is checked by [`validate_audio`] and audio refs only for being set, files
+/// they point to are checked by `literacy validate`.
+pub fn validate(text: &Text) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    if text.format_version > FORMAT_VERSION {
+        diagnostics.push(Diagnostic {
+            segment: None,
+            word: None,
+            issue: Issue::UnsupportedVersion(text.format_version),
+        });
+    }
+    if text.segments.is_empty() {
+        diagnostics.push(Diagnostic {
+            segment: None,
+            word: None,
+            issue: Issue::NoSegments,
+        });
+    }
+    for (i, segment) in text.segments.iter().enumerate() {
+        validate_segment(segment, i, &mut diagnostics);
+    }
+    diagnostics
+}
+
+/// Decodes the embedded audio of every segment, which takes a while for
+/// long texts.
+pub fn validate_audio(text: &Text) -> Vec<Diagnostic> {
+    text.segments
+        .iter()
+        .enumerate()
+        .filter_map(|(i, segment)| match &segment.audio {
+            Audio::Wav(wav) => wav.decode().err().map(|e| Diagnostic {
+                segment: Some(i),
+                word: None,
+                issue: Issue::UndecodableAudio(e.to_string()),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn validate_segment(segment: &Segment, index: usize, diagnostics: &mut Vec<Diagnostic>) {
+    // timestamps in seconds of a shared file are checked like relative ones
+    let relative;
+    let segment = match &segment.audio {
+        Audio::Window(window) if window.absolute => {
+            let mut s = segment.clone();
+            s.make_timestamps_relative();
+            relative = s;
+            &relative
+        }
+        _ => segment,
+    };
+    let mut push = |word: Option<usize>, issue: Issue| {
+        diagnostics.push(Diagnostic {
+            segment: Some(index),
+            word,
+            issue,
+        })
+    };
+
+    if segment.words.is_empty() {
+        push(None, Issue::EmptySegment);
+    }
+    if !segment.duration.is_finite() || segment.duration < 0.0 {
+        push(None, Issue::InvalidDuration(segment.duration));
+    }
+    match &segment.audio {
+        Audio::None => push(None, Issue::MissingAudio),
+        Audio::Ref(r) if r.trim().is_empty() => push(None, Issue::EmptyAudioRef),
+        Audio::Ref(_) | Audio::Wav(_) => {}
+        Audio::Window(window) => {
+            if window.file.trim().is_empty() {
+                push(None, Issue::EmptyAudioRef);
+            }
+            let (start, end) = (window.start, window.end);
+            if !start.is_finite() || !end.is_finite() || start < 0.0 || end <= start {
+                push(None, Issue::InvalidAudioWindow { start, end });
+            }
+        }
+    }
+
+    if let Some(Block::Heading(level)) = segment.block
+        && !(1..=6).contains(&level)
+    {
+        push(None, Issue::InvalidHeadingLevel(level));
+    }
+    for mark in &segment.marks {
+        if mark.start >= mark.end || mark.end > segment.words.len() {
+            push(
+                None,
+                Issue::InvalidMark {
+                    start: mark.start,
+                    end: mark.end,
+                },
+            );
+        }
+    }
+    for &i in &segment.line_breaks {
+        if i >= segment.words.len() {
+            push(None, Issue::InvalidLineBreak(i));
+        }
+    }
+
+    let timestamped = segment
+        .words
+        .iter()
+        .filter(|w| matches!(w, Word::Timestamped { .. }))
+        .count();
+    if timestamped != 0 && timestamped != segment.words.len() {
+        push(None, Issue::MixedWords);
+    }
+
+    let mut previous: Option<(f64, f64)> = None;
+    for (i, word) in segment.words.iter().enumerate() {
+        let (content, start, end) = match word {
+            Word::Raw(content) => {
+                if content.trim().is_empty() {
+                    push(Some(i), Issue::EmptyWord);
+                }
+                continue;
+            }
+            Word::Timestamped {
+                content,
+                start,
+                end,
+            } => (content, *start, *end),
+        };
+        if content.trim().is_empty() {
+            push(Some(i), Issue::EmptyWord);
+        }
+        if !start.is_finite() || !end.is_finite() || start < 0.0 {
+            push(Some(i), Issue::InvalidTimestamp { start, end });
+            continue;
+        }
+        if end < start {
+            push(Some(i), Issue::EndBeforeStart { start, end });
+        }
+        if segment.duration.is_finite() && end > segment.duration {
+            push(
+                Some(i),
+                Issue::BeyondDuration {
+                    end,
+                    duration: segment.duration,
+                },
+            );
+        }
+        if let Some((previous_start, previous_end)) = previous {
+            if start < previous_start {
+                push(
+                    Some(i),
+                    Issue::NotMonotonic {
+                        previous_start,
+                        start,
+                    },
+                );
+            } else if start < previous_end {
+                push(
+                    Some(i),
+                    Issue::Overlap {
+                        previous_end,
+                        start,
+                    },
+                );
+            }
+        }
+        previous = Some((start, end));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metadata, Wav};
+
+    fn timed(content: &str, start: f64, end: f64) -> Word {
+        Word::Timestamped {
+            content: content.to_string(),
+            start,
+            end,
+        }
+    }
+
+    fn segment(words: Vec<Word>, duration: f64) -> Segment {
+        Segment {
+            words,
+            audio: Audio::Ref("0.wav".to_string()),
+            duration,
+            ..Default::default()
+        }
+    }
+
+    fn issues(segments: Vec<Segment>) -> Vec<(Option<usize>, Issue)> {
+        let text = Text {
+            format_version: FORMAT_VERSION,
+            metadata: Metadata::default(),
+            segments,
+        };
+        validate(&text)
+            .into_iter()
+            .map(|d| (d.word, d.issue))
+            .collect()
+    }
+
+    #[test]
+    fn valid_segment() {
+        let words = vec![timed("eins", 0.0, 0.5), timed("zwei", 0.5, 1.0)];
+        assert_eq!(issues(vec![segment(words, 1.0)]), []);
+    }
+
+    #[test]
+    fn overlap() {
+        let words = vec![timed("eins", 0.0, 0.6), timed("zwei", 0.5, 1.0)];
+        assert_eq!(
+            issues(vec![segment(words, 1.0)]),
+            [(
+                Some(1),
+                Issue::Overlap {
+                    previous_end: 0.6,
+                    start: 0.5
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn not_monotonic() {
+        let words = vec![timed("eins", 0.5, 0.6), timed("zwei", 0.2, 1.0)];
+        assert_eq!(
+            issues(vec![segment(words, 1.0)]),
+            [(
+                Some(1),
+                Issue::NotMonotonic {
+                    previous_start: 0.5,
+                    start: 0.2
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn end_before_start() {
+        let words = vec![timed("eins", 0.5, 0.4)];
+        assert_eq!(
+            issues(vec![segment(words, 1.0)]),
+            [(
+                Some(0),
+                Issue::EndBeforeStart {
+                    start: 0.5,
+                    end: 0.4
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn beyond_duration() {
+        let words = vec![timed("eins", 0.0, 1.5)];
+        assert_eq!(
+            issues(vec![segment(words, 1.0)]),
+            [(
+                Some(0),
+                Issue::BeyondDuration {
+                    end: 1.5,
+                    duration: 1.0
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(issues(vec![]), [(None, Issue::NoSegments)]);
+        assert_eq!(
+            issues(vec![segment(vec![], 1.0)]),
+            [(None, Issue::EmptySegment)]
+        );
+        let words = vec![Word::Raw("eins".to_string()), Word::Raw(" ".to_string())];
+        assert_eq!(
+            issues(vec![segment(words, 1.0)]),
+            [(Some(1), Issue::EmptyWord)]
+        );
+    }
+
+    #[test]
+    fn mixed() {
+        let words = vec![Word::Raw("eins".to_string()), timed("zwei", 0.5, 1.0)];
+        assert_eq!(
+            issues(vec![segment(words, 1.0)]),
+            [(None, Issue::MixedWords)]
+        );
+    }
+
+    #[test]
+    fn embedded_audio_is_only_decoded_by_validate_audio() {
+        let mut broken = segment(vec![Word::Raw("eins".to_string())], 1.0);
+        broken.audio = Audio::Wav(Wav::Base64("kein base64!".to_string()));
+        let mut fine = broken.clone();
+        fine.audio = Audio::Wav(Wav::compressed(b"RIFF"));
+        let text = Text {
+            format_version: FORMAT_VERSION,
+            metadata: Metadata::default(),
+            segments: vec![fine, broken],
+        };
+
+        assert_eq!(validate(&text), []);
+        let diagnostics = validate_audio(&text);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].segment, Some(1));
+        assert!(matches!(diagnostics[0].issue, Issue::UndecodableAudio(_)));
+    }
+}
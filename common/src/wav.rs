@@ -0,0 +1,114 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use miniz_oxide::{deflate::compress_to_vec_zlib, inflate::decompress_to_vec_zlib};
+use thiserror::Error;
+
+use crate::Wav;
+
+const COMPRESSION_LEVEL: u8 = 9;
+
+#[derive(Error, Debug)]
+pub enum WavError {
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("invalid zlib stream: {0}")]
+    Compression(String),
+}
+
+impl Wav {
+    pub fn raw(bytes: Vec<u8>) -> Self {
+        Wav::Raw(bytes)
+    }
+
+    pub fn compressed(bytes: &[u8]) -> Self {
+        Wav::Compressed(compress(bytes))
+    }
+
+    pub fn base64(bytes: &[u8]) -> Self {
+        Wav::Base64(STANDARD.encode(bytes))
+    }
+
+    pub fn base64_compressed(bytes: &[u8]) -> Self {
+        Wav::Base64Compressed(STANDARD.encode(compress(bytes)))
+    }
+
+    /// Returns the audio file bytes, undoing any encoding of the variant.
+    pub fn decode(&self) -> Result<Vec<u8>, WavError> {
+        match self {
+            Wav::Raw(bytes) => Ok(bytes.clone()),
+            Wav::Compressed(bytes) => decompress(bytes),
+            Wav::Base64(string) => Ok(STANDARD.decode(string)?),
+            Wav::Base64Compressed(string) => decompress(&STANDARD.decode(string)?),
+        }
+    }
+}
+
+fn compress(bytes: &[u8]) -> Vec<u8> {
+    compress_to_vec_zlib(bytes, COMPRESSION_LEVEL)
+}
+
+fn decompress(bytes: &[u8]) -> Result<Vec<u8>, WavError> {
+    decompress_to_vec_zlib(bytes).map_err(|e| WavError::Compression(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A header and some repetitive samples, like audio files are.
+    fn file() -> Vec<u8> {
+        let mut bytes = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        bytes.extend((0..4096u32).map(|i| (i % 64) as u8));
+        bytes
+    }
+
+    #[test]
+    fn variants_round_trip() {
+        let bytes = file();
+        for wav in [
+            Wav::raw(bytes.clone()),
+            Wav::compressed(&bytes),
+            Wav::base64(&bytes),
+            Wav::base64_compressed(&bytes),
+        ] {
+            assert_eq!(wav.decode().unwrap(), bytes, "{wav:?}");
+            // and through the document format
+            let json = serde_json::to_string(&wav).unwrap();
+            let parsed: Wav = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.decode().unwrap(), bytes, "{json}");
+        }
+    }
+
+    #[test]
+    fn compressed_variants_are_smaller() {
+        let bytes = file();
+        let Wav::Compressed(compressed) = Wav::compressed(&bytes) else {
+            unreachable!();
+        };
+        assert!(compressed.len() < bytes.len() / 4);
+        // the zlib header, other decoders of the format rely on it
+        assert_eq!(compressed[0], 0x78);
+    }
+
+    #[test]
+    fn empty_files_round_trip() {
+        assert_eq!(Wav::compressed(&[]).decode().unwrap(), Vec::<u8>::new());
+        assert_eq!(Wav::base64(&[]).decode().unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn invalid_encodings_are_errors() {
+        assert!(matches!(
+            Wav::Base64("not base64!".into()).decode(),
+            Err(WavError::Base64(_))
+        ));
+        assert!(matches!(
+            Wav::Compressed(vec![1, 2, 3]).decode(),
+            Err(WavError::Compression(_))
+        ));
+        // valid base64 of something that isn't zlib
+        assert!(matches!(
+            Wav::Base64Compressed(STANDARD.encode(b"plain")).decode(),
+            Err(WavError::Compression(_))
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use app::library::{CONTENT_ROUTE, LIBRARY_ROUTE};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{StatusCode, Uri, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use common::{LibraryEntry, Metadata};
+use leptos::logging;
+use serde::Deserialize;
+use tower_http::services::ServeDir;
+
+const CONTENT_DIR_VAR: &str = "LITERACY_CONTENT_DIR";
+const DEFAULT_CONTENT_DIR: &str = "content";
+const TEXT_FILE: &str = "text.json";
+
+pub fn content_dir() -> PathBuf {
+    std::env::var_os(CONTENT_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CONTENT_DIR))
+}
+
+/// Serves the text folders of `content_dir` and a listing of all texts in it,
+/// sorted by title.
+///
+/// Every folder directly inside `content_dir` that contains a `text.json` is
+/// a text, its folder name is the id of the text. Unless the folder of a text
+/// contains them, `text.vtt` and `text.srt` subtitles are generated from the
+/// `text.json`.
+pub fn router<S>(content_dir: PathBuf) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let library = Arc::new(Library {
+        content_dir,
+        metadata: Mutex::default(),
+    });
+    let content = ServeDir::new(library.content_dir.as_path())
+        .fallback(get(subtitles).with_state(library.clone()));
+    Router::new()
+        .route(LIBRARY_ROUTE, get(list))
+        .nest_service(CONTENT_ROUTE, content)
+        .with_state(library)
+}
+
+struct Library {
+    content_dir: PathBuf,
+    /// Metadata of the texts by id and when their `text.json` was modified,
+    /// so listing only reads the documents that changed.
+    metadata: Mutex<HashMap<String, (SystemTime, Metadata)>>,
+}
+
+async fn list(State(library): State<Arc<Library>>) -> Json<Vec<LibraryEntry>> {
+    Json(discover(&library).await)
+}
+
+async fn subtitles(State(library): State<Arc<Library>>, uri: Uri) -> Response {
+    let Some((id, file)) = uri.path().trim_start_matches('/').split_once('/') else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    // ids are directory names, which may need escaping in the url
+    let Some(id) = percent_decode(id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let vtt = match file {
+        "text.vtt" => true,
+        "text.srt" => false,
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+    if id.starts_with('.') || id.contains(['/', '\\']) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let path = library.content_dir.join(&id).join(TEXT_FILE);
+    let Ok(json) = tokio::fs::read_to_string(path).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let text = match common::migrate::from_str(&json) {
+        Ok(text) => text,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    if vtt {
+        (
+            [(CONTENT_TYPE, "text/vtt; charset=utf-8")],
+            common::subtitle::to_vtt(&text, true),
+        )
+            .into_response()
+    } else {
+        (
+            [(CONTENT_TYPE, "application/x-subrip; charset=utf-8")],
+            common::subtitle::to_srt(&text),
+        )
+            .into_response()
+    }
+}
+
+/// Decodes `%XX` escapes of a path segment, `None` if they are malformed or
+/// don't decode to utf-8.
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail
+                .get(..2)
+                .filter(|h| h.iter().all(u8::is_ascii_hexdigit))?;
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+async fn discover(library: &Library) -> Vec<LibraryEntry> {
+    let mut entries = vec![];
+    let content_dir = &library.content_dir;
+    let Ok(mut dir) = tokio::fs::read_dir(content_dir).await else {
+        logging::warn!("content directory {} not readable", content_dir.display());
+        return entries;
+    };
+    let mut cache = HashMap::new();
+    while let Ok(Some(e)) = dir.next_entry().await {
+        let path = e.path().join(TEXT_FILE);
+        let Ok(modified) = tokio::fs::metadata(&path).await.and_then(|m| m.modified()) else {
+            continue;
+        };
+        let Some(id) = e.file_name().to_str().map(|s| s.to_string()) else {
+            continue;
+        };
+        let cached = library.metadata.lock().unwrap().get(&id).cloned();
+        let metadata = match cached {
+            Some((time, metadata)) if time == modified => metadata,
+            _ => {
+                let Ok(json) = tokio::fs::read_to_string(&path).await else {
+                    continue;
+                };
+                match serde_json::from_str::<TextHead>(&json) {
+                    Ok(head) => head.metadata,
+                    Err(err) => {
+                        logging::warn!("metadata of text {id} not readable: {err}");
+                        Metadata::default()
+                    }
+                }
+            }
+        };
+        cache.insert(id.clone(), (modified, metadata.clone()));
+        entries.push(LibraryEntry { id, metadata });
+    }
+    // texts removed since are dropped
+    *library.metadata.lock().unwrap() = cache;
+    entries.sort_by(|a, b| {
+        a.title()
+            .to_lowercase()
+            .cmp(&b.title().to_lowercase())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    entries
+}
+
+/// The part of a text document needed for the listing.
+#[derive(Deserialize)]
+struct TextHead {
+    #[serde(default)]
+    metadata: Metadata,
+}
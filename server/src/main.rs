@@ -3,6 +3,9 @@ use leptos::logging::log;
 use leptos::prelude::*;
 use leptos_axum::{LeptosRoutes, generate_route_list};
 
+mod library;
+mod position;
+
 #[tokio::main]
 async fn main() {
     let conf = get_configuration(None).unwrap();
@@ -16,6 +19,11 @@ async fn main() {
             let leptos_options = leptos_options.clone();
             move || app::shell(leptos_options.clone())
         })
+        .merge(library::router(library::content_dir()))
+        .merge(position::router(
+            position::positions_dir(),
+            position::user_header(),
+        ))
         .fallback(leptos_axum::file_and_error_handler(app::shell))
         .with_state(leptos_options);
 
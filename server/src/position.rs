@@ -0,0 +1,125 @@
+use std::{path::PathBuf, sync::Arc};
+
+use app::position::POSITIONS_ROUTE;
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use common::Position;
+use leptos::logging;
+
+const POSITIONS_DIR_VAR: &str = "LITERACY_POSITIONS_DIR";
+const DEFAULT_POSITIONS_DIR: &str = "positions";
+const USER_HEADER_VAR: &str = "LITERACY_USER_HEADER";
+
+pub fn positions_dir() -> PathBuf {
+    std::env::var_os(POSITIONS_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_POSITIONS_DIR))
+}
+
+/// Header with the name of the signed in user, set by an authenticating
+/// reverse proxy.
+pub fn user_header() -> Option<HeaderName> {
+    let name = std::env::var(USER_HEADER_VAR).ok()?;
+    match HeaderName::try_from(name) {
+        Ok(header) => Some(header),
+        Err(err) => {
+            logging::warn!("{USER_HEADER_VAR} is not a header name: {err}");
+            None
+        }
+    }
+}
+
+struct Store {
+    dir: PathBuf,
+    user_header: Option<HeaderName>,
+}
+
+/// Keeps the reading position of every user per text in
+/// `positions_dir/<user>/<text id>.json`.
+///
+/// Without a `user_header` positions are not kept and every request is
+/// answered with not found, the reader then only keeps them in the browser.
+pub fn router<S>(positions_dir: PathBuf, user_header: Option<HeaderName>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let store = Arc::new(Store {
+        dir: positions_dir,
+        user_header,
+    });
+    Router::new()
+        .route(&format!("{POSITIONS_ROUTE}/:text_id"), get(load).put(save))
+        .with_state(store)
+}
+
+impl Store {
+    fn file(&self, headers: &HeaderMap, text_id: &str) -> Result<PathBuf, StatusCode> {
+        let header = self.user_header.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+        let user = headers
+            .get(header)
+            .and_then(|u| u.to_str().ok())
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        if !is_file_name(user) || !is_file_name(text_id) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Ok(self.dir.join(user).join(format!("{text_id}.json")))
+    }
+}
+
+/// Whether `name` can be used as a single path component.
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', '\0'])
+}
+
+async fn load(
+    State(store): State<Arc<Store>>,
+    Path(text_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let file = match store.file(&headers, &text_id) {
+        Ok(file) => file,
+        Err(status) => return status.into_response(),
+    };
+    let Ok(json) = tokio::fs::read_to_string(&file).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match serde_json::from_str::<Position>(&json) {
+        Ok(position) => Json(position).into_response(),
+        Err(err) => {
+            logging::warn!("position {} not readable: {err}", file.display());
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+async fn save(
+    State(store): State<Arc<Store>>,
+    Path(text_id): Path<String>,
+    headers: HeaderMap,
+    Json(position): Json<Position>,
+) -> StatusCode {
+    let file = match store.file(&headers, &text_id) {
+        Ok(file) => file,
+        Err(status) => return status,
+    };
+    let json = serde_json::to_string(&position).expect("position is serializable");
+    match write(&file, &json).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(err) => {
+            logging::warn!("position {} not writable: {err}", file.display());
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn write(file: &std::path::Path, json: &str) -> std::io::Result<()> {
+    if let Some(dir) = file.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(file, json).await
+}